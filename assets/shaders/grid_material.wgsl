@@ -0,0 +1,20 @@
+#import bevy_pbr::mesh_view_bindings
+
+struct GridMaterial {
+    color: vec4<f32>,
+    fade_start: f32,
+    fade_end: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: GridMaterial;
+
+@fragment
+fn fragment(
+    #import bevy_pbr::mesh_vertex_output
+) -> @location(0) vec4<f32> {
+    // lines fade out between fade_start and fade_end units away from the camera
+    let distance = length(world_position.xyz - view.world_position);
+    let fade = 1.0 - smoothstep(material.fade_start, material.fade_end, distance);
+    return vec4<f32>(material.color.rgb, material.color.a * fade);
+}
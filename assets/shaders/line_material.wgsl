@@ -9,5 +9,10 @@ var<uniform> material: LineMaterial;
 fn fragment(
     #import bevy_pbr::mesh_vertex_output
 ) -> @location(0) vec4<f32> {
+#ifdef VERTEX_COLORS
+    // set by the mesh pipeline when the mesh has a color attribute
+    return material.color * color;
+#else
     return material.color;
+#endif
 }
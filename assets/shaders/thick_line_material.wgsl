@@ -0,0 +1,112 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_bindings
+
+struct ThickLineMaterial {
+    color: vec4<f32>,
+    width: f32,
+    world_width: u32,
+    dash_length: f32,
+    gap_length: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: ThickLineMaterial;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) other_end: vec3<f32>,
+    // x: the side of the line, -1 or 1, y: the distance along the line
+    @location(2) side_distance: vec2<f32>,
+#ifdef VERTEX_COLORS
+    @location(3) color: vec4<f32>,
+#endif
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) distance: f32,
+#ifdef VERTEX_COLORS
+    @location(1) color: vec4<f32>,
+#endif
+};
+
+
+// moves a point that is behind the camera along the line towards the other end, so the line's
+// direction on screen still makes sense
+fn clip_to_near(point: vec4<f32>, other: vec4<f32>) -> vec4<f32> {
+    let min_w = 0.0001;
+    if (point.w >= min_w || abs(other.w - point.w) < min_w) {
+        return point;
+    }
+    return mix(point, other, (min_w - point.w) / (other.w - point.w));
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let side = vertex.side_distance.x;
+    let world_position = mesh.model * vec4<f32>(vertex.position, 1.0);
+    let world_other = mesh.model * vec4<f32>(vertex.other_end, 1.0);
+
+    var clip_position: vec4<f32>;
+    if (material.world_width == 1u) {
+        // push the vertex out in the world, square to the line and to the view direction
+        let to_camera = view.world_position - world_position.xyz;
+        var normal = cross(world_other.xyz - world_position.xyz, to_camera);
+        if (length(normal) < 0.000001) {
+            normal = vec3<f32>(0.0, 1.0, 0.0);
+        }
+        let offset = normalize(normal) * side * 0.5 * material.width;
+        clip_position = view.view_proj * vec4<f32>(world_position.xyz + offset, 1.0);
+    } else {
+        // push the vertex out on screen, square to the line's direction there
+        let clip = view.view_proj * world_position;
+        let clip_other = view.view_proj * world_other;
+        clip_position = clip_to_near(clip, clip_other);
+        let other = clip_to_near(clip_other, clip);
+
+        let half_viewport = 0.5 * view.viewport.zw;
+        let screen = clip_position.xy / clip_position.w * half_viewport;
+        let screen_other = other.xy / other.w * half_viewport;
+        var direction = screen_other - screen;
+        if (length(direction) < 0.0001) {
+            direction = vec2<f32>(1.0, 0.0);
+        }
+        direction = normalize(direction);
+        let offset = vec2<f32>(-direction.y, direction.x) * side * 0.5 * material.width;
+        clip_position = vec4<f32>(
+            clip_position.xy + offset / half_viewport * clip_position.w,
+            clip_position.zw
+        );
+    }
+
+    var out: VertexOutput;
+    out.clip_position = clip_position;
+    out.distance = vertex.side_distance.y;
+#ifdef VERTEX_COLORS
+    out.color = vertex.color;
+#endif
+    return out;
+}
+
+struct FragmentInput {
+    @location(0) distance: f32,
+#ifdef VERTEX_COLORS
+    @location(1) color: vec4<f32>,
+#endif
+};
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    if (material.dash_length > 0.0) {
+        let period = material.dash_length + material.gap_length;
+        if (in.distance - floor(in.distance / period) * period > material.dash_length) {
+            discard;
+        }
+    }
+
+#ifdef VERTEX_COLORS
+    return material.color * in.color;
+#else
+    return material.color;
+#endif
+}
@@ -0,0 +1,692 @@
+//! Command line parsing for the scene viewer.
+//!
+//! Parsing is kept free of any Bevy state so it can be exercised without building an `App`.
+
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::axis_gizmo::AxisLength;
+use crate::bounds::BoundsMode;
+use crate::ground_grid::MAJOR_LINE_EVERY;
+use crate::report::ReportFormat;
+
+pub const DEFAULT_SCENE_PATH: &str = "assets/models/alien.glb";
+/// What `--validate` and `--thumbnails` go through when no models are given, every glTF and glb
+/// file in the library.
+pub const DEFAULT_LIBRARY_PATH: &str = "assets/models/*.gl*";
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+pub const DEFAULT_TURNTABLE_FRAMES: u32 = 36;
+pub const DEFAULT_WINDOW_WIDTH: f32 = 1280.0;
+pub const DEFAULT_WINDOW_HEIGHT: f32 = 720.0;
+pub const DEFAULT_MODEL_SPACING: f32 = 1.0;
+pub const DEFAULT_GRID_SPACING: f32 = 1.0;
+
+/// Which camera controller is spawned once the scene has loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CameraMode {
+    /// Orbit around the scene focus with the mouse, see `PanOrbitCamera`.
+    #[default]
+    Orbit,
+    /// Free flying WASD camera, see `CameraController`.
+    Fly,
+}
+
+impl CameraMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "orbit" => Some(CameraMode::Orbit),
+            "fly" => Some(CameraMode::Fly),
+            _ => None,
+        }
+    }
+}
+
+/// The lighting used when the scene does not bring lights of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LightingPreset {
+    /// A directional 'sun' light with a low ambient term.
+    #[default]
+    Sun,
+    /// A directional light with a strong ambient term, useful for dark models.
+    Bright,
+    /// Ambient light only.
+    Ambient,
+    /// No lights are added at all, only lights from the file are used.
+    None,
+}
+
+impl LightingPreset {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "sun" => Some(LightingPreset::Sun),
+            "bright" => Some(LightingPreset::Bright),
+            "ambient" => Some(LightingPreset::Ambient),
+            "none" => Some(LightingPreset::None),
+            _ => None,
+        }
+    }
+
+    pub fn ambient_brightness(&self) -> f32 {
+        match self {
+            LightingPreset::Sun => 1.0 / 5.0,
+            LightingPreset::Bright => 0.6,
+            LightingPreset::Ambient => 1.0,
+            LightingPreset::None => 0.0,
+        }
+    }
+
+    pub fn has_directional_light(&self) -> bool {
+        matches!(self, LightingPreset::Sun | LightingPreset::Bright)
+    }
+}
+
+/// Picks a scene from a glTF file either by its position in the file or by its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl SceneSelector {
+    fn parse(value: &str) -> Self {
+        match value.parse() {
+            Ok(index) => SceneSelector::Index(index),
+            Err(_) => SceneSelector::Name(value.to_string()),
+        }
+    }
+}
+
+/// A model to load, as given on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelPath {
+    /// Path to the glTF file, without any `#SceneN` label. May contain `*` and `?` wildcards
+    /// in the file name.
+    pub path: String,
+    /// Scene to spawn, from `path#SceneN` or `--scene N|NAME`. The file's default scene when `None`.
+    pub scene: Option<SceneSelector>,
+}
+
+impl ModelPath {
+    fn parse(value: &str) -> Result<Self, ArgsError> {
+        // 'model.gltf#Scene1' selects a scene the same way an asset label does
+        match value.rsplit_once("#Scene") {
+            Some((path, index)) => Ok(ModelPath {
+                path: path.to_string(),
+                scene: Some(SceneSelector::Index(parse_number("#Scene", index)?)),
+            }),
+            None => Ok(ModelPath {
+                path: value.to_string(),
+                scene: None,
+            }),
+        }
+    }
+}
+
+/// Options given on the command line. Inserted as a resource before the app starts.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ViewerArgs {
+    /// Models to show side by side, never empty.
+    pub models: Vec<ModelPath>,
+    /// Gap between models laid out on the grid, in world units.
+    pub model_spacing: f32,
+    pub camera_mode: CameraMode,
+    pub lighting: LightingPreset,
+    pub window_width: f32,
+    pub window_height: f32,
+    pub shadows: bool,
+    pub axes: bool,
+    pub axis_length: AxisLength,
+    /// Show the small axes in the corner that turn with the camera.
+    pub orientation_gizmo: bool,
+    pub grid: bool,
+    /// Distance between the minor lines of the ground grid, in world units.
+    pub grid_spacing: f32,
+    pub bounds_mode: BoundsMode,
+    /// Write the `--validate` results in this format, implies `--validate`.
+    pub report: Option<ReportFormat>,
+    /// Where the report goes, stdout when `None`.
+    pub report_file: Option<String>,
+    /// Save a screenshot here once the models are framed, then quit.
+    pub screenshot: Option<String>,
+    /// Screenshot size in pixels, the window size when `None`.
+    pub screenshot_size: Option<UVec2>,
+    /// Screenshots have a transparent background.
+    pub transparent: bool,
+    /// Write a thumbnail of each model and an index to this folder, then quit.
+    pub thumbnails: Option<String>,
+    /// Width and height of the thumbnails in pixels.
+    pub thumbnail_size: u32,
+    /// Save the frames of one turn of the orbit camera to this folder, then quit.
+    pub turntable: Option<String>,
+    /// Frames in an exported turn.
+    pub turntable_frames: u32,
+    /// Camera height above the focus during a turn in degrees, the current view's when `None`.
+    pub turntable_elevation: Option<f32>,
+    /// Camera distance from the focus during a turn, the current distance when `None`.
+    pub turntable_radius: Option<f32>,
+    /// Also assemble the exported frames into a GIF.
+    pub turntable_gif: bool,
+}
+
+impl Default for ViewerArgs {
+    fn default() -> Self {
+        ViewerArgs {
+            models: vec![ModelPath {
+                path: DEFAULT_SCENE_PATH.to_string(),
+                scene: None,
+            }],
+            model_spacing: DEFAULT_MODEL_SPACING,
+            camera_mode: CameraMode::default(),
+            lighting: LightingPreset::default(),
+            window_width: DEFAULT_WINDOW_WIDTH,
+            window_height: DEFAULT_WINDOW_HEIGHT,
+            shadows: true,
+            axes: true,
+            axis_length: AxisLength::default(),
+            orientation_gizmo: true,
+            grid: true,
+            grid_spacing: DEFAULT_GRID_SPACING,
+            bounds_mode: BoundsMode::default(),
+            report: None,
+            report_file: None,
+            screenshot: None,
+            screenshot_size: None,
+            transparent: false,
+            thumbnails: None,
+            thumbnail_size: DEFAULT_THUMBNAIL_SIZE,
+            turntable: None,
+            turntable_frames: DEFAULT_TURNTABLE_FRAMES,
+            turntable_elevation: None,
+            turntable_radius: None,
+            turntable_gif: false,
+        }
+    }
+}
+
+/// What the command line asked the viewer to do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Run(ViewerArgs),
+    /// Load the models without a window and report what is wrong with them.
+    Validate(ViewerArgs),
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgsError {
+    UnknownOption(String),
+    MissingValue(&'static str),
+    InvalidValue { option: &'static str, value: String },
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgsError::UnknownOption(option) => write!(f, "unknown option '{}'", option),
+            ArgsError::MissingValue(option) => write!(f, "option '{}' requires a value", option),
+            ArgsError::InvalidValue { option, value } => {
+                write!(f, "invalid value '{}' for option '{}'", value, option)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+/// Parses the arguments following the program name.
+pub fn parse_args<I, S>(args: I) -> Result<Command, ArgsError>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut viewer_args = ViewerArgs::default();
+    let mut models = Vec::new();
+    let mut scene: Option<SceneSelector> = None;
+    let mut validate = false;
+    let mut args = args.into_iter().map(Into::into);
+
+    while let Some(arg) = args.next() {
+        // allow both '--option value' and '--option=value'
+        let (option, inline_value) = match arg.split_once('=') {
+            Some((option, value)) if arg.starts_with("--") => {
+                (option.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &'static str| {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or(ArgsError::MissingValue(name))
+        };
+
+        match option.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--validate" => validate = true,
+            "--scene" => {
+                let value = value("--scene")?;
+                scene = Some(SceneSelector::parse(&value));
+            }
+            "--camera" => {
+                let value = value("--camera")?;
+                viewer_args.camera_mode =
+                    CameraMode::parse(&value).ok_or(ArgsError::InvalidValue {
+                        option: "--camera",
+                        value,
+                    })?;
+            }
+            "--lighting" => {
+                let value = value("--lighting")?;
+                viewer_args.lighting =
+                    LightingPreset::parse(&value).ok_or(ArgsError::InvalidValue {
+                        option: "--lighting",
+                        value,
+                    })?;
+            }
+            "--window" => {
+                let value = value("--window")?;
+                let (width, height) = parse_size("--window", &value)?;
+                viewer_args.window_width = width as f32;
+                viewer_args.window_height = height as f32;
+            }
+            "--screenshot" => viewer_args.screenshot = Some(value("--screenshot")?),
+            "--screenshot-size" => {
+                let value = value("--screenshot-size")?;
+                let (width, height) = parse_size("--screenshot-size", &value)?;
+                viewer_args.screenshot_size = Some(UVec2::new(width, height));
+            }
+            "--transparent" => viewer_args.transparent = true,
+            "--thumbnails" => viewer_args.thumbnails = Some(value("--thumbnails")?),
+            "--thumbnail-size" => {
+                let value = value("--thumbnail-size")?;
+                viewer_args.thumbnail_size = value
+                    .parse()
+                    .ok()
+                    .filter(|size: &u32| *size > 0)
+                    .ok_or(ArgsError::InvalidValue {
+                        option: "--thumbnail-size",
+                        value,
+                    })?;
+            }
+            "--turntable" => viewer_args.turntable = Some(value("--turntable")?),
+            "--turntable-frames" => {
+                let value = value("--turntable-frames")?;
+                viewer_args.turntable_frames = value
+                    .parse()
+                    .ok()
+                    .filter(|frames: &u32| *frames > 0)
+                    .ok_or(ArgsError::InvalidValue {
+                        option: "--turntable-frames",
+                        value,
+                    })?;
+            }
+            "--turntable-elevation" => {
+                let value = value("--turntable-elevation")?;
+                // straight above or below the focus the camera has no direction to turn in
+                viewer_args.turntable_elevation = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|elevation: &f32| elevation.abs() < 90.0)
+                        .ok_or(ArgsError::InvalidValue {
+                            option: "--turntable-elevation",
+                            value,
+                        })?,
+                );
+            }
+            "--turntable-radius" => {
+                let value = value("--turntable-radius")?;
+                viewer_args.turntable_radius = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|radius: &f32| *radius > 0.0)
+                        .ok_or(ArgsError::InvalidValue {
+                            option: "--turntable-radius",
+                            value,
+                        })?,
+                );
+            }
+            "--turntable-gif" => viewer_args.turntable_gif = true,
+            "--spacing" => {
+                let value = value("--spacing")?;
+                viewer_args.model_spacing = value
+                    .parse()
+                    .ok()
+                    .filter(|spacing: &f32| *spacing >= 0.0)
+                    .ok_or(ArgsError::InvalidValue {
+                        option: "--spacing",
+                        value,
+                    })?;
+            }
+            "--axes-length" => {
+                let value = value("--axes-length")?;
+                viewer_args.axis_length = match value.as_str() {
+                    "bounds" => AxisLength::SceneBounds,
+                    _ => AxisLength::Units(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|length: &f32| *length > 0.0)
+                            .ok_or(ArgsError::InvalidValue {
+                                option: "--axes-length",
+                                value,
+                            })?,
+                    ),
+                };
+            }
+            "--grid-spacing" => {
+                let value = value("--grid-spacing")?;
+                viewer_args.grid_spacing = value
+                    .parse()
+                    .ok()
+                    .filter(|spacing: &f32| *spacing > 0.0)
+                    .ok_or(ArgsError::InvalidValue {
+                        option: "--grid-spacing",
+                        value,
+                    })?;
+            }
+            "--report" => {
+                let value = value("--report")?;
+                viewer_args.report = Some(ReportFormat::parse(&value).ok_or(
+                    ArgsError::InvalidValue {
+                        option: "--report",
+                        value,
+                    },
+                )?);
+                validate = true;
+            }
+            "--report-file" => viewer_args.report_file = Some(value("--report-file")?),
+            "--shadows" => viewer_args.shadows = true,
+            "--no-shadows" => viewer_args.shadows = false,
+            "--axes" => viewer_args.axes = true,
+            "--no-axes" => viewer_args.axes = false,
+            "--orientation-gizmo" => viewer_args.orientation_gizmo = true,
+            "--no-orientation-gizmo" => viewer_args.orientation_gizmo = false,
+            "--grid" => viewer_args.grid = true,
+            "--no-grid" => viewer_args.grid = false,
+            "--vertex-bounds" => viewer_args.bounds_mode = BoundsMode::Vertices,
+            _ if option.starts_with('-') => return Err(ArgsError::UnknownOption(option)),
+            _ => models.push(ModelPath::parse(&arg)?),
+        }
+    }
+
+    if !models.is_empty() {
+        viewer_args.models = models;
+    } else if validate || viewer_args.thumbnails.is_some() {
+        viewer_args.models = vec![ModelPath {
+            path: DEFAULT_LIBRARY_PATH.to_string(),
+            scene: None,
+        }];
+    }
+    // --scene applies to every file that doesn't have its own '#SceneN' label
+    for model in &mut viewer_args.models {
+        if model.scene.is_none() {
+            model.scene = scene.clone();
+        }
+    }
+
+    if validate {
+        return Ok(Command::Validate(viewer_args));
+    }
+    if viewer_args.thumbnails.is_some() {
+        // a square window frames the models the way the thumbnails show them
+        viewer_args.window_width = viewer_args.thumbnail_size as f32;
+        viewer_args.window_height = viewer_args.thumbnail_size as f32;
+        viewer_args.grid = false;
+        viewer_args.axes = false;
+    }
+    if viewer_args.turntable.is_some() {
+        // the turn is made by the orbit camera
+        viewer_args.camera_mode = CameraMode::Orbit;
+    }
+    Ok(Command::Run(viewer_args))
+}
+
+fn parse_number(option: &'static str, value: &str) -> Result<usize, ArgsError> {
+    value.parse().map_err(|_| ArgsError::InvalidValue {
+        option,
+        value: value.to_string(),
+    })
+}
+
+/// A `WxH` size in pixels.
+fn parse_size(option: &'static str, value: &str) -> Result<(u32, u32), ArgsError> {
+    let invalid = || ArgsError::InvalidValue {
+        option,
+        value: value.to_string(),
+    };
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    let width: u32 = width.parse().map_err(|_| invalid())?;
+    let height: u32 = height.parse().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok((width, height))
+}
+
+pub fn print_help() {
+    println!(
+        "
+Usage: my_scene_viewer [OPTIONS] [PATH[#SceneN]]...
+
+Arguments:
+    PATH                glTF or glb files to load side by side, '*' and '?' match
+                        any characters in the file name, a folder loads every model in it
+                        [default: {}]
+
+Options:
+    --scene N|NAME      index or name of the scene in the file to spawn [default: the file's default scene]
+    --camera MODE       initial camera: orbit, fly [default: orbit]
+    --lighting PRESET   lights added when the file has none: sun, bright, ambient, none [default: sun]
+    --spacing UNITS     gap between models when loading several [default: {}]
+    --window WxH        window size in pixels [default: {}x{}]
+    --grid-spacing UNITS
+                        distance between ground grid lines, every {}th line is brighter [default: {}]
+    --shadows, --no-shadows
+                        turn shadows from the default light on or off [default: on]
+    --axes-length UNITS|bounds
+                        length of the axis arrows at the origin, 'bounds' to reach past the models [default: 1]
+    --axes, --no-axes   show or hide the axis arrows at the origin [default: show]
+    --orientation-gizmo, --no-orientation-gizmo
+                        show or hide the axes in the corner that turn with the camera [default: show]
+    --grid, --no-grid   show or hide the ground grid at startup [default: show]
+    --vertex-bounds     compute model bounds from every vertex instead of the mesh boxes
+    --validate          load the models without a window, print what is wrong with them and exit
+                        with status 1 on any error [default PATH: {}]
+    --report json|csv   also write the statistics and problems of each model in this format,
+                        implies --validate
+    --report-file FILE  write the report to FILE instead of stdout
+    --screenshot FILE   save a PNG of the view once the models are framed, then quit
+    --screenshot-size WxH
+                        size of screenshots in pixels [default: the window size]
+    --transparent       give screenshots a transparent background
+    --thumbnails DIR    save a thumbnail of each model, without the grid and axes, to DIR along
+                        with index.html and index.json, then quit [default PATH: {}]
+    --thumbnail-size N  width and height of the thumbnails in pixels [default: {}]
+    --turntable DIR     orbit the camera once around the models and save each frame to DIR as
+                        frame_0000.png, frame_0001.png, ..., then quit
+    --turntable-frames N
+                        frames in the turn [default: {}]
+    --turntable-elevation DEG
+                        camera height above the focus during a turn [default: the current view's]
+    --turntable-radius UNITS
+                        camera distance from the focus during a turn [default: the current distance]
+    --turntable-gif     also assemble the frames into DIR/turntable.gif
+    -h, --help          print this help
+
+Controls:
+    MOUSE       - Move camera orientation
+    LClick/M    - Enable mouse movement
+    WSAD        - forward/back/strafe left/right
+    LShift      - 'run'
+    E           - up
+    Q           - down
+    L           - animate light direction
+    U           - toggle shadows
+    F           - frame the loaded models
+    C           - cycle through the camera controller and any cameras loaded from the scene
+
+    Tab         - switch to the next scene in the file
+    PgUp/PgDn   - load the previous/next model in the same folder
+    B           - open/close the model browser
+    H           - open/close the hierarchy of the loaded models
+    I           - show/hide the model statistics
+    K           - open/close the material editor, Ctrl + LClick picks a mesh
+    P           - save a screenshot as screenshot-DATE-TIME.png
+    J           - turn the orbit camera once around the models, again to stop
+    1-9         - move the orbit camera to a bookmarked view, Ctrl + 1-9 bookmarks the current view
+    0           - open/close the list of bookmarks, kept in MODEL.bookmarks.json
+    O           - show/hide the scene and mesh bounds
+    G           - show/hide the ground grid
+    N/T/Y       - show/hide vertex normals/tangents/bitangents
+    V           - show/hide the wireframe
+
+    Space       - Play/Pause animation
+    Enter       - Cycle through animations
+",
+        DEFAULT_SCENE_PATH,
+        DEFAULT_MODEL_SPACING,
+        DEFAULT_WINDOW_WIDTH,
+        DEFAULT_WINDOW_HEIGHT,
+        MAJOR_LINE_EVERY,
+        DEFAULT_GRID_SPACING,
+        DEFAULT_LIBRARY_PATH,
+        DEFAULT_LIBRARY_PATH,
+        DEFAULT_THUMBNAIL_SIZE,
+        DEFAULT_TURNTABLE_FRAMES
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> ViewerArgs {
+        match parse_args(args.iter().copied()) {
+            Ok(Command::Run(viewer_args)) => viewer_args,
+            other => panic!("expected Command::Run for {:?}, got {:?}", args, other),
+        }
+    }
+
+    #[test]
+    fn scene_label() {
+        let viewer_args = run(&["file.gltf#Scene1"]);
+        assert_eq!(
+            viewer_args.models,
+            vec![ModelPath {
+                path: "file.gltf".to_string(),
+                scene: Some(SceneSelector::Index(1)),
+            }]
+        );
+    }
+
+    #[test]
+    fn scene_label_wins_over_scene_option() {
+        let viewer_args = run(&["--scene", "Main", "a.glb#Scene2", "b.glb"]);
+        assert_eq!(viewer_args.models[0].scene, Some(SceneSelector::Index(2)));
+        assert_eq!(
+            viewer_args.models[1].scene,
+            Some(SceneSelector::Name("Main".to_string()))
+        );
+    }
+
+    #[test]
+    fn invalid_scene_label() {
+        assert_eq!(
+            parse_args(["file.gltf#SceneX"]),
+            Err(ArgsError::InvalidValue {
+                option: "#Scene",
+                value: "X".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn option_with_equals_or_separate_value() {
+        assert_eq!(run(&["--spacing=2.5"]).model_spacing, 2.5);
+        assert_eq!(run(&["--spacing", "2.5"]).model_spacing, 2.5);
+        let viewer_args = run(&["--window=800x600"]);
+        assert_eq!(
+            (viewer_args.window_width, viewer_args.window_height),
+            (800.0, 600.0)
+        );
+    }
+
+    #[test]
+    fn negative_numbers() {
+        assert_eq!(
+            run(&["--turntable-elevation", "-20"]).turntable_elevation,
+            Some(-20.0)
+        );
+        assert_eq!(
+            run(&["--turntable-elevation=-20"]).turntable_elevation,
+            Some(-20.0)
+        );
+        assert_eq!(
+            parse_args(["--spacing", "-1"]),
+            Err(ArgsError::InvalidValue {
+                option: "--spacing",
+                value: "-1".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_numbers() {
+        assert_eq!(
+            parse_args(["--grid-spacing", "wide"]),
+            Err(ArgsError::InvalidValue {
+                option: "--grid-spacing",
+                value: "wide".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_args(["--window", "800x0"]),
+            Err(ArgsError::InvalidValue {
+                option: "--window",
+                value: "800x0".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn missing_value() {
+        assert_eq!(
+            parse_args(["--camera"]),
+            Err(ArgsError::MissingValue("--camera"))
+        );
+    }
+
+    #[test]
+    fn unknown_option() {
+        assert_eq!(
+            parse_args(["--frobnicate"]),
+            Err(ArgsError::UnknownOption("--frobnicate".to_string()))
+        );
+        assert_eq!(
+            parse_args(["--frobnicate=1"]),
+            Err(ArgsError::UnknownOption("--frobnicate".to_string()))
+        );
+    }
+
+    #[test]
+    fn help() {
+        assert_eq!(parse_args(["--help"]), Ok(Command::Help));
+        assert_eq!(parse_args(["a.glb", "-h", "--bogus"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(run(&[]), ViewerArgs::default());
+    }
+
+    #[test]
+    fn flags() {
+        let viewer_args = run(&["--no-shadows", "--no-grid", "--grid", "--no-axes"]);
+        assert!(!viewer_args.shadows);
+        assert!(viewer_args.grid);
+        assert!(!viewer_args.axes);
+    }
+}
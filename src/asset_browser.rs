@@ -0,0 +1,156 @@
+//! A side panel listing the models in the assets folder. Clicking one replaces the loaded models.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::args::ModelPath;
+use crate::scene_setup::{model_files, LoadModels, SceneHandle};
+
+pub const MODELS_DIR: &str = "assets/models";
+
+#[derive(Resource)]
+pub struct AssetBrowser {
+    pub open: bool,
+    directory: String,
+    /// Asset paths of the models in `directory`.
+    files: Vec<String>,
+    /// Name prefixes shared by more than one file, e.g. `corridor` for `corridor_wall.glb`.
+    prefixes: Vec<String>,
+    prefix: Option<String>,
+    filter: String,
+}
+
+impl Default for AssetBrowser {
+    fn default() -> Self {
+        let mut asset_browser = AssetBrowser {
+            open: false,
+            directory: MODELS_DIR.to_string(),
+            files: Vec::new(),
+            prefixes: Vec::new(),
+            prefix: None,
+            filter: String::new(),
+        };
+        asset_browser.rescan();
+        asset_browser
+    }
+}
+
+impl AssetBrowser {
+    pub fn rescan(&mut self) {
+        self.files = model_files(&self.directory);
+
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for prefix in self
+            .files
+            .iter()
+            .filter_map(|path| name_prefix(file_name(path)))
+        {
+            *counts.entry(prefix).or_default() += 1;
+        }
+        self.prefixes = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(prefix, _)| prefix.to_string())
+            .collect();
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let name = file_name(path);
+        let prefix_matches = match &self.prefix {
+            Some(prefix) => name_prefix(name) == Some(prefix.as_str()),
+            None => true,
+        };
+        prefix_matches && name.to_lowercase().contains(&self.filter.to_lowercase())
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// The part of a file name before the first underscore, `rocket` for `rocket_baseA.glb`.
+fn name_prefix(name: &str) -> Option<&str> {
+    name.split_once('_').map(|(prefix, _)| prefix)
+}
+
+pub fn toggle_asset_browser(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut asset_browser: ResMut<AssetBrowser>,
+) {
+    // don't close the panel while typing a filter
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::B) {
+        asset_browser.open = !asset_browser.open;
+    }
+}
+
+pub fn show_asset_browser(
+    mut contexts: EguiContexts,
+    mut asset_browser: ResMut<AssetBrowser>,
+    scene_handles: Query<&SceneHandle>,
+    mut load_models: EventWriter<LoadModels>,
+) {
+    if !asset_browser.open {
+        return;
+    }
+    let asset_browser = &mut *asset_browser;
+
+    egui::SidePanel::right("asset_browser")
+        .default_width(240.0)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.heading(&asset_browser.directory);
+                if ui.small_button("Rescan").clicked() {
+                    asset_browser.rescan();
+                }
+            });
+
+            ui.horizontal_wrapped(|ui| {
+                if ui
+                    .selectable_label(asset_browser.prefix.is_none(), "all")
+                    .clicked()
+                {
+                    asset_browser.prefix = None;
+                }
+                for prefix in &asset_browser.prefixes {
+                    let selected = asset_browser.prefix.as_ref() == Some(prefix);
+                    if ui
+                        .selectable_label(selected, format!("{}_", prefix))
+                        .clicked()
+                    {
+                        asset_browser.prefix = Some(prefix.clone());
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Filter");
+                ui.text_edit_singleline(&mut asset_browser.filter);
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for path in asset_browser
+                    .files
+                    .iter()
+                    .filter(|path| asset_browser.matches(path))
+                {
+                    let loaded = scene_handles
+                        .iter()
+                        .any(|scene_handle| scene_handle.path() == path);
+                    if ui.selectable_label(loaded, file_name(path)).clicked() {
+                        load_models.send(LoadModels(vec![ModelPath {
+                            path: path.clone(),
+                            scene: None,
+                        }]));
+                    }
+                }
+            });
+        });
+}
@@ -0,0 +1,264 @@
+//! Arrows along the world X, Y and Z axes at the origin, and a small copy of them in the corner
+//! of the window that turns with the camera, so the orientation is clear wherever the camera is.
+
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{camera::Viewport, primitives::Aabb, view::RenderLayers},
+    window::PrimaryWindow,
+};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::args::ViewerArgs;
+use crate::bounds::SceneBounds;
+use crate::shapes::Arrow;
+use crate::world_label::{active_camera, world_label};
+
+/// Render layer of the corner gizmo, so the main cameras don't see it and its camera sees
+/// nothing else.
+const ORIENTATION_GIZMO_LAYER: u8 = 1;
+/// Size of the corner gizmo's viewport, and its distance from the window's bottom left corner,
+/// in logical pixels.
+const ORIENTATION_GIZMO_SIZE: f32 = 100.0;
+const ORIENTATION_GIZMO_MARGIN: f32 = 10.0;
+
+/// How long the arrows of the gizmo at the origin are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AxisLength {
+    Units(f32),
+    /// Reach just past the farthest side of the scene bounds.
+    SceneBounds,
+}
+
+impl Default for AxisLength {
+    fn default() -> Self {
+        AxisLength::Units(1.0)
+    }
+}
+
+impl AxisLength {
+    /// The length in world units, one unit for `SceneBounds` until something has loaded.
+    pub fn length(&self, scene_bounds: Option<&Aabb>) -> f32 {
+        match (self, scene_bounds) {
+            (AxisLength::Units(length), _) => *length,
+            (AxisLength::SceneBounds, Some(aabb)) => {
+                let farthest = Vec3::from(aabb.min())
+                    .abs()
+                    .max(Vec3::from(aabb.max()).abs());
+                1.1 * farthest.max_element().max(f32::EPSILON)
+            }
+            (AxisLength::SceneBounds, None) => 1.0,
+        }
+    }
+}
+
+/// The arrow color, label and direction of each axis.
+const AXES: [(Color, &str, Vec3); 3] = [
+    (Color::rgb(0.96, 0.20, 0.20), "X", Vec3::X),
+    (Color::rgb(0.63, 0.96, 0.26), "Y", Vec3::Y),
+    (Color::rgb(0.20, 0.20, 0.96), "Z", Vec3::Z),
+];
+
+/// The root of a set of axis arrows. The arrows are one unit long before the root's scale.
+#[derive(Component)]
+pub struct AxisGizmo {
+    pub length: AxisLength,
+}
+
+#[derive(Component)]
+pub struct OrientationGizmoCamera;
+
+/// Spawns unit length arrows along X, Y and Z under a new root entity, all on `render_layers`.
+pub fn spawn_axis_gizmo(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    render_layers: RenderLayers,
+) -> Entity {
+    let arrow = meshes.add(Mesh::from(Arrow::default()));
+
+    commands
+        .spawn((
+            SpatialBundle::default(),
+            render_layers,
+            Name::new("Axis gizmo"),
+        ))
+        .with_children(|parent| {
+            for (color, label, direction) in AXES {
+                // the arrow points up Y, turn it to its axis
+                parent.spawn((
+                    PbrBundle {
+                        mesh: arrow.clone(),
+                        material: materials.add(StandardMaterial {
+                            base_color: color,
+                            unlit: true,
+                            ..default()
+                        }),
+                        transform: Transform::from_rotation(Quat::from_rotation_arc(
+                            Vec3::Y,
+                            direction,
+                        )),
+                        ..default()
+                    },
+                    render_layers,
+                    Name::new(format!("{} axis", label)),
+                ));
+            }
+        })
+        .id()
+}
+
+/// Spawns the gizmo at the origin, and the corner gizmo with its camera.
+pub fn setup_axis_gizmos(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    viewer_args: Res<ViewerArgs>,
+) {
+    if viewer_args.axes {
+        let gizmo = spawn_axis_gizmo(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            RenderLayers::default(),
+        );
+        commands.entity(gizmo).insert(AxisGizmo {
+            length: viewer_args.axis_length,
+        });
+    }
+
+    if viewer_args.orientation_gizmo {
+        let layer = RenderLayers::layer(ORIENTATION_GIZMO_LAYER);
+        spawn_axis_gizmo(&mut commands, &mut meshes, &mut materials, layer);
+        commands.spawn((
+            Camera3dBundle {
+                camera: Camera {
+                    // drawn over the main camera, update_orientation_gizmo moves the viewport
+                    // to the corner
+                    order: 1,
+                    viewport: Some(Viewport {
+                        physical_size: UVec2::splat(ORIENTATION_GIZMO_SIZE as u32),
+                        ..default()
+                    }),
+                    ..default()
+                },
+                camera_3d: Camera3d {
+                    clear_color: ClearColorConfig::None,
+                    ..default()
+                },
+                ..default()
+            },
+            layer,
+            OrientationGizmoCamera,
+            Name::new("Orientation gizmo camera"),
+        ));
+    }
+}
+
+/// Sizes the gizmo at the origin when it is spawned, and again when it follows the scene bounds
+/// and they change.
+pub fn scale_axis_gizmo(
+    scene_bounds: Res<SceneBounds>,
+    mut gizmos: Query<(&AxisGizmo, &mut Transform)>,
+    added: Query<(), Added<AxisGizmo>>,
+) {
+    if !scene_bounds.is_changed() && added.is_empty() {
+        return;
+    }
+    for (gizmo, mut transform) in &mut gizmos {
+        transform.scale = Vec3::splat(gizmo.length.length(scene_bounds.aabb()));
+    }
+}
+
+/// Keeps the corner gizmo's viewport in the bottom left corner and turns its camera the same way
+/// as the active camera.
+pub fn update_orientation_gizmo(
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), Without<OrientationGizmoCamera>>,
+    mut gizmo_cameras: Query<(&mut Camera, &mut Transform), With<OrientationGizmoCamera>>,
+) {
+    let Ok(window) = primary_window.get_single() else {
+        return;
+    };
+    let rotation = match active_camera(&cameras) {
+        Some((_, camera_transform)) => camera_transform.to_scale_rotation_translation().1,
+        None => Quat::IDENTITY,
+    };
+
+    let scale_factor = window.scale_factor() as f32;
+    let size = (ORIENTATION_GIZMO_SIZE * scale_factor) as u32;
+    let margin = (ORIENTATION_GIZMO_MARGIN * scale_factor) as u32;
+    let viewport = Viewport {
+        physical_position: UVec2::new(
+            margin,
+            window.physical_height().saturating_sub(size + margin),
+        ),
+        physical_size: UVec2::splat(size),
+        ..default()
+    };
+
+    for (mut camera, mut transform) in &mut gizmo_cameras {
+        // only touch the camera when something changed, to keep change detection quiet
+        if camera
+            .viewport
+            .as_ref()
+            .map(|current| (current.physical_position, current.physical_size))
+            != Some((viewport.physical_position, viewport.physical_size))
+        {
+            camera.viewport = Some(viewport.clone());
+        }
+        let gizmo_transform =
+            Transform::from_translation(rotation * Vec3::Z * 4.0).with_rotation(rotation);
+        if *transform != gizmo_transform {
+            *transform = gizmo_transform;
+        }
+    }
+}
+
+/// X, Y and Z next to the arrow heads of both gizmos.
+pub fn show_axis_labels(
+    mut contexts: EguiContexts,
+    gizmos: Query<&GlobalTransform, With<AxisGizmo>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    gizmo_cameras: Query<(&Camera, &GlobalTransform), With<OrientationGizmoCamera>>,
+) {
+    let ctx = contexts.ctx_mut();
+    let axis_label = |label: &str, color: Color| {
+        let [r, g, b, _] = color.as_rgba_f32();
+        egui::RichText::new(label)
+            .strong()
+            .color(egui::Color32::from_rgb(
+                (r * 255.0) as u8,
+                (g * 255.0) as u8,
+                (b * 255.0) as u8,
+            ))
+    };
+
+    if let Some(camera) = active_camera(&cameras) {
+        for (index, gizmo) in gizmos.iter().enumerate() {
+            for (color, label, direction) in AXES {
+                world_label(
+                    ctx,
+                    ("axis_label", index, label),
+                    camera,
+                    gizmo.transform_point(direction * 1.1),
+                    axis_label(label, color),
+                );
+            }
+        }
+    }
+
+    // the corner gizmo sits at the origin of its own render layer
+    for (index, camera) in gizmo_cameras.iter().enumerate() {
+        for (color, label, direction) in AXES {
+            world_label(
+                ctx,
+                ("orientation_label", index, label),
+                camera,
+                direction * 1.2,
+                axis_label(label, color),
+            );
+        }
+    }
+}
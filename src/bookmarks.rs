@@ -0,0 +1,515 @@
+//! Named orbit camera poses on the number keys, kept in a file next to the model so everyone
+//! looking at it can share the same views.
+//!
+//! Ctrl + 1-9 stores the camera's focus, distance and rotation, 1-9 glides back to it, and 0 lists
+//! the bookmarks to rename or delete them. The bookmarks of `alien.glb` are kept in
+//! `alien.glb.bookmarks.json`; with several models loaded they go with the first one by path.
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::camera_pan_orbit::PanOrbitCamera;
+use crate::scene_setup::{asset_folder, SceneHandle};
+use crate::turntable::Turntable;
+
+/// Version of the bookmark file layout, bumped whenever it changes.
+pub const BOOKMARKS_SCHEMA_VERSION: u32 = 1;
+
+/// How long the camera takes to glide to a bookmark, in seconds.
+const RECALL_SECONDS: f32 = 0.6;
+
+/// The keys of bookmarks 1 to 9.
+const SLOT_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// Where an orbit camera is, the camera sits `radius` behind `focus` along `rotation`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraPose {
+    pub focus: Vec3,
+    pub radius: f32,
+    pub rotation: Quat,
+}
+
+impl CameraPose {
+    pub fn of(pan_orbit: &PanOrbitCamera, transform: &Transform) -> Self {
+        CameraPose {
+            focus: pan_orbit.focus,
+            radius: pan_orbit.radius,
+            rotation: transform.rotation,
+        }
+    }
+
+    /// The pose `t` of the way from this one to `other`.
+    pub fn lerp(&self, other: &CameraPose, t: f32) -> Self {
+        CameraPose {
+            focus: self.focus.lerp(other.focus, t),
+            radius: self.radius + (other.radius - self.radius) * t,
+            rotation: self.rotation.slerp(other.rotation, t),
+        }
+    }
+
+    fn apply(&self, pan_orbit: &mut PanOrbitCamera, transform: &mut Transform) {
+        pan_orbit.focus = self.focus;
+        pan_orbit.radius = self.radius;
+        pan_orbit.upside_down = (self.rotation * Vec3::Y).y <= 0.0;
+        transform.rotation = self.rotation;
+        transform.translation = self.focus + self.rotation * Vec3::new(0.0, 0.0, self.radius);
+    }
+}
+
+/// A stored pose, as it is written to the bookmark file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    /// The number key it is on, 1 to 9.
+    pub slot: u8,
+    pub name: String,
+    pub focus: [f32; 3],
+    pub radius: f32,
+    /// Quaternion as x, y, z, w.
+    pub rotation: [f32; 4],
+}
+
+impl CameraBookmark {
+    fn new(slot: u8, name: String, pose: &CameraPose) -> Self {
+        CameraBookmark {
+            slot,
+            name,
+            focus: pose.focus.to_array(),
+            radius: pose.radius,
+            rotation: pose.rotation.to_array(),
+        }
+    }
+
+    pub fn pose(&self) -> CameraPose {
+        CameraPose {
+            focus: Vec3::from(self.focus),
+            radius: self.radius,
+            // files edited by hand may not be quite normalized
+            rotation: Quat::from_array(self.rotation).normalize(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct BookmarkFile {
+    schema_version: u32,
+    /// The model the bookmarks were made for, for whoever reads the file.
+    model: String,
+    bookmarks: Vec<CameraBookmark>,
+}
+
+/// The bookmarks in a bookmark file, sorted by slot. Slots outside 1-9 are dropped, as are all
+/// but the first bookmark on a slot. Files from a newer version of the viewer are rejected.
+fn parse_bookmarks(json: &str) -> Result<Vec<CameraBookmark>, String> {
+    let file = serde_json::from_str::<BookmarkFile>(json).map_err(|error| error.to_string())?;
+    if file.schema_version > BOOKMARKS_SCHEMA_VERSION {
+        return Err(format!(
+            "schema version {} is newer than this viewer's {}",
+            file.schema_version, BOOKMARKS_SCHEMA_VERSION
+        ));
+    }
+    let mut bookmarks: Vec<CameraBookmark> = file
+        .bookmarks
+        .into_iter()
+        .filter(|bookmark| (1..=9).contains(&bookmark.slot))
+        .collect();
+    bookmarks.sort_by_key(|bookmark| bookmark.slot);
+    bookmarks.dedup_by_key(|bookmark| bookmark.slot);
+    Ok(bookmarks)
+}
+
+/// `assets/models/alien.glb.bookmarks.json` for `assets/models/alien.glb`.
+pub fn bookmark_file(model: &str) -> PathBuf {
+    Path::new(&asset_folder()).join(format!("{}.bookmarks.json", model))
+}
+
+/// A glide to a bookmark. It starts from wherever the camera is on the first frame.
+struct Recall {
+    from: Option<CameraPose>,
+    to: CameraPose,
+    elapsed: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct CameraBookmarks {
+    pub open: bool,
+    /// The model the bookmarks belong to, `None` while nothing is loaded.
+    model: Option<String>,
+    /// Sorted by slot.
+    bookmarks: Vec<CameraBookmark>,
+    recall: Option<Recall>,
+    /// The bookmark file could not be read, so it isn't overwritten.
+    read_only: bool,
+}
+
+impl CameraBookmarks {
+    /// Switches to the bookmarks of `model`, none if it doesn't have a bookmark file.
+    fn load(&mut self, model: Option<&str>) {
+        self.model = model.map(str::to_string);
+        self.bookmarks.clear();
+        self.recall = None;
+        self.read_only = false;
+        let Some(model) = model else {
+            return;
+        };
+
+        let path = bookmark_file(model);
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+            Err(error) => {
+                warn!("Could not read {}: {}", path.display(), error);
+                self.read_only = true;
+                return;
+            }
+        };
+        match parse_bookmarks(&json) {
+            Ok(bookmarks) => {
+                self.bookmarks = bookmarks;
+                info!(
+                    "Loaded {} camera bookmarks from {}",
+                    self.bookmarks.len(),
+                    path.display()
+                );
+            }
+            Err(error) => {
+                warn!("Could not read {}: {}", path.display(), error);
+                self.read_only = true;
+            }
+        }
+    }
+
+    /// Writes the bookmarks to the model's bookmark file, or removes the file when there are none.
+    fn save(&self) {
+        let Some(model) = &self.model else {
+            return;
+        };
+        let path = bookmark_file(model);
+        if self.read_only {
+            warn!(
+                "Not saving the camera bookmarks over {}, it could not be read",
+                path.display()
+            );
+            return;
+        }
+        let saved = if self.bookmarks.is_empty() {
+            match std::fs::remove_file(&path) {
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                removed => removed.map_err(|error| error.to_string()),
+            }
+        } else {
+            let file = BookmarkFile {
+                schema_version: BOOKMARKS_SCHEMA_VERSION,
+                model: model.clone(),
+                bookmarks: self.bookmarks.clone(),
+            };
+            serde_json::to_string_pretty(&file)
+                .map_err(|error| error.to_string())
+                .and_then(|json| {
+                    std::fs::write(&path, json + "\n").map_err(|error| error.to_string())
+                })
+        };
+        if let Err(error) = saved {
+            error!(
+                "Could not save the camera bookmarks to {}: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+
+    /// Puts `pose` on `slot`, keeping the name of a bookmark already there.
+    fn store(&mut self, slot: u8, pose: &CameraPose) {
+        match self
+            .bookmarks
+            .iter_mut()
+            .find(|bookmark| bookmark.slot == slot)
+        {
+            Some(bookmark) => *bookmark = CameraBookmark::new(slot, bookmark.name.clone(), pose),
+            None => {
+                self.bookmarks
+                    .push(CameraBookmark::new(slot, format!("View {}", slot), pose));
+                self.bookmarks.sort_by_key(|bookmark| bookmark.slot);
+            }
+        }
+        self.save();
+    }
+
+    /// Starts gliding to the bookmark on `slot`, false if there is none.
+    fn recall(&mut self, slot: u8) -> bool {
+        let Some(bookmark) = self.bookmarks.iter().find(|bookmark| bookmark.slot == slot) else {
+            return false;
+        };
+        self.recall = Some(Recall {
+            from: None,
+            to: bookmark.pose(),
+            elapsed: 0.0,
+        });
+        true
+    }
+}
+
+/// Switches to the bookmarks of whichever models are loaded.
+pub fn load_camera_bookmarks(
+    scene_handles: Query<&SceneHandle>,
+    mut camera_bookmarks: ResMut<CameraBookmarks>,
+) {
+    let model = scene_handles.iter().map(SceneHandle::path).min();
+    if model != camera_bookmarks.model.as_deref() {
+        camera_bookmarks.load(model);
+    }
+}
+
+/// Ctrl + 1-9 stores the active orbit camera's pose, 1-9 glides back to it and 0 shows the list.
+pub fn camera_bookmark_keys(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut camera_bookmarks: ResMut<CameraBookmarks>,
+    mut turntable: ResMut<Turntable>,
+    cameras: Query<(&Camera, &PanOrbitCamera, &Transform)>,
+) {
+    // digits typed into a bookmark name aren't shortcuts
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Key0) {
+        camera_bookmarks.open = !camera_bookmarks.open;
+    }
+    let Some(slot) = SLOT_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key))
+        .map(|index| index as u8 + 1)
+    else {
+        return;
+    };
+
+    if keyboard_input.any_pressed([KeyCode::LControl, KeyCode::RControl]) {
+        if camera_bookmarks.model.is_none() {
+            return;
+        }
+        let Some((_, pan_orbit, transform)) = cameras.iter().find(|(camera, ..)| camera.is_active)
+        else {
+            info!("Camera bookmarks are stored from the orbit camera");
+            return;
+        };
+        camera_bookmarks.store(slot, &CameraPose::of(pan_orbit, transform));
+        info!("Stored camera bookmark {}", slot);
+    } else if camera_bookmarks.recall(slot) {
+        turntable.stop();
+    }
+}
+
+/// Glides the active orbit camera to the bookmark being recalled, easing in and out. Orbiting with
+/// the mouse stops it.
+pub fn recall_camera_bookmark(
+    time: Res<Time>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut camera_bookmarks: ResMut<CameraBookmarks>,
+    mut cameras: Query<(&Camera, &mut PanOrbitCamera, &mut Transform)>,
+) {
+    let camera_bookmarks = &mut *camera_bookmarks;
+    let Some(recall) = &mut camera_bookmarks.recall else {
+        return;
+    };
+    let Some((_, mut pan_orbit, mut transform)) =
+        cameras.iter_mut().find(|(camera, ..)| camera.is_active)
+    else {
+        camera_bookmarks.recall = None;
+        return;
+    };
+    if mouse_input.pressed(MouseButton::Right) {
+        camera_bookmarks.recall = None;
+        return;
+    }
+
+    let from = *recall
+        .from
+        .get_or_insert_with(|| CameraPose::of(&pan_orbit, &transform));
+    recall.elapsed += time.delta_seconds();
+    let t = (recall.elapsed / RECALL_SECONDS).min(1.0);
+    let eased = t * t * (3.0 - 2.0 * t);
+    from.lerp(&recall.to, eased)
+        .apply(&mut pan_orbit, &mut transform);
+    if t >= 1.0 {
+        camera_bookmarks.recall = None;
+    }
+}
+
+/// The list of bookmarks, to rename, recall or delete them.
+pub fn show_camera_bookmarks(
+    mut contexts: EguiContexts,
+    mut camera_bookmarks: ResMut<CameraBookmarks>,
+    mut turntable: ResMut<Turntable>,
+) {
+    if !camera_bookmarks.open {
+        return;
+    }
+    let camera_bookmarks = &mut *camera_bookmarks;
+    let mut recall = None;
+    let mut delete = None;
+    let mut renamed = false;
+
+    egui::Window::new("Bookmarks")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let Some(model) = &camera_bookmarks.model else {
+                ui.label("No model loaded");
+                return;
+            };
+            if camera_bookmarks.bookmarks.is_empty() {
+                ui.label("Ctrl + 1-9 stores the current view");
+            }
+            egui::Grid::new("camera_bookmarks")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for bookmark in &mut camera_bookmarks.bookmarks {
+                        ui.label(bookmark.slot.to_string());
+                        renamed |= ui.text_edit_singleline(&mut bookmark.name).lost_focus();
+                        ui.horizontal(|ui| {
+                            if ui.small_button("Go").clicked() {
+                                recall = Some(bookmark.slot);
+                            }
+                            if ui.small_button("Delete").clicked() {
+                                delete = Some(bookmark.slot);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            ui.small(bookmark_file(model).display().to_string());
+        });
+
+    if let Some(slot) = delete {
+        camera_bookmarks
+            .bookmarks
+            .retain(|bookmark| bookmark.slot != slot);
+    }
+    if renamed || delete.is_some() {
+        camera_bookmarks.save();
+    }
+    if let Some(slot) = recall {
+        if camera_bookmarks.recall(slot) {
+            turntable.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn pose() -> CameraPose {
+        CameraPose {
+            focus: Vec3::new(1.0, 2.0, 3.0),
+            radius: 4.0,
+            rotation: Quat::from_rotation_y(0.5),
+        }
+    }
+
+    fn bookmark(slot: u8, name: &str) -> CameraBookmark {
+        CameraBookmark::new(slot, name.to_string(), &pose())
+    }
+
+    fn file_json(schema_version: u32, bookmarks: Vec<CameraBookmark>) -> String {
+        serde_json::to_string(&BookmarkFile {
+            schema_version,
+            model: "models/alien.glb".to_string(),
+            bookmarks,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn lerp_ends_on_each_pose() {
+        let from = pose();
+        let to = CameraPose {
+            focus: Vec3::new(-1.0, 0.0, 5.0),
+            radius: 10.0,
+            rotation: Quat::from_rotation_x(-FRAC_PI_2),
+        };
+
+        assert_eq!(from.lerp(&to, 0.0), from);
+        let end = from.lerp(&to, 1.0);
+        assert!(end.focus.abs_diff_eq(to.focus, 1e-6));
+        assert_eq!(end.radius, to.radius);
+        assert!(end.rotation.abs_diff_eq(to.rotation, 1e-6));
+
+        let halfway = from.lerp(&to, 0.5);
+        assert!(halfway.focus.abs_diff_eq(Vec3::new(0.0, 1.0, 4.0), 1e-6));
+        assert_eq!(halfway.radius, 7.0);
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let file = BookmarkFile {
+            schema_version: BOOKMARKS_SCHEMA_VERSION,
+            model: "models/alien.glb".to_string(),
+            bookmarks: vec![bookmark(1, "Front"), bookmark(3, "Top \"close\"")],
+        };
+        let json = serde_json::to_string_pretty(&file).unwrap();
+        assert_eq!(serde_json::from_str::<BookmarkFile>(&json).unwrap(), file);
+        assert_eq!(file.bookmarks[0].pose(), pose());
+    }
+
+    #[test]
+    fn parse_drops_slots_outside_1_to_9_and_duplicates() {
+        let json = file_json(
+            BOOKMARKS_SCHEMA_VERSION,
+            vec![
+                bookmark(4, "Four"),
+                bookmark(0, "Zero"),
+                bookmark(2, "Two"),
+                bookmark(10, "Ten"),
+                bookmark(4, "Four again"),
+                bookmark(9, "Nine"),
+            ],
+        );
+        let names: Vec<_> = parse_bookmarks(&json)
+            .unwrap()
+            .into_iter()
+            .map(|bookmark| (bookmark.slot, bookmark.name))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (2, "Two".to_string()),
+                (4, "Four".to_string()),
+                (9, "Nine".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_newer_files() {
+        let older = file_json(0, vec![bookmark(1, "Front")]);
+        assert_eq!(parse_bookmarks(&older).unwrap().len(), 1);
+
+        let newer = file_json(BOOKMARKS_SCHEMA_VERSION + 1, vec![bookmark(1, "Front")]);
+        assert_eq!(
+            parse_bookmarks(&newer).unwrap_err(),
+            format!(
+                "schema version {} is newer than this viewer's {}",
+                BOOKMARKS_SCHEMA_VERSION + 1,
+                BOOKMARKS_SCHEMA_VERSION
+            )
+        );
+
+        assert!(parse_bookmarks("{\"bookmarks\": []}").is_err());
+    }
+}
@@ -0,0 +1,443 @@
+//! Axis aligned bounds of meshes and spawned scenes.
+//!
+//! Mesh bounds are found by transforming the eight corners of each mesh's `Aabb`, or optionally
+//! every vertex position, so rotated meshes don't inflate the result. Skinned meshes are placed by
+//! their joints rather than by their own transform, the same way the GPU skins them.
+
+use bevy::{
+    ecs::system::SystemParam,
+    math::Vec3A,
+    prelude::*,
+    render::{
+        mesh::{
+            skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
+            VertexAttributeValues,
+        },
+        primitives::Aabb,
+        view::NoFrustumCulling,
+    },
+    scene::InstanceId,
+};
+
+/// How precisely mesh bounds are computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoundsMode {
+    /// Transform the corners of each mesh's local `Aabb`. Exact for unrotated meshes and tight for
+    /// rotated ones.
+    #[default]
+    Corners,
+    /// Transform every vertex position. Exact, but touches every vertex of every mesh.
+    Vertices,
+}
+
+/// The combined bounds of the loaded models, as placed on the grid. Set by `setup_scene_after_load`.
+#[derive(Resource, Default)]
+pub struct SceneBounds {
+    aabb: Option<Aabb>,
+}
+
+impl SceneBounds {
+    pub fn aabb(&self) -> Option<&Aabb> {
+        self.aabb.as_ref()
+    }
+
+    pub fn set(&mut self, aabb: Option<Aabb>) {
+        self.aabb = aabb;
+    }
+}
+
+/// Grows a box point by point or box by box.
+#[derive(Debug, Clone, Copy)]
+pub struct BoundsBuilder {
+    min: Vec3A,
+    max: Vec3A,
+}
+
+impl Default for BoundsBuilder {
+    fn default() -> Self {
+        BoundsBuilder {
+            min: Vec3A::splat(f32::MAX),
+            max: Vec3A::splat(f32::MIN),
+        }
+    }
+}
+
+impl BoundsBuilder {
+    pub fn add_point(&mut self, point: Vec3A) {
+        self.min = self.min.min(point);
+        self.max = self.max.max(point);
+    }
+
+    pub fn add_aabb(&mut self, aabb: &Aabb) {
+        self.min = self.min.min(aabb.min());
+        self.max = self.max.max(aabb.max());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    /// The box around everything added, `None` if nothing was.
+    pub fn build(&self) -> Option<Aabb> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(Aabb::from_min_max(
+            Vec3::from(self.min),
+            Vec3::from(self.max),
+        ))
+    }
+}
+
+/// The eight corners of a box.
+pub fn aabb_corners(aabb: &Aabb) -> [Vec3A; 8] {
+    let (c, h) = (aabb.center, aabb.half_extents);
+    [
+        c + h * Vec3A::new(-1.0, -1.0, -1.0),
+        c + h * Vec3A::new(1.0, -1.0, -1.0),
+        c + h * Vec3A::new(-1.0, 1.0, -1.0),
+        c + h * Vec3A::new(1.0, 1.0, -1.0),
+        c + h * Vec3A::new(-1.0, -1.0, 1.0),
+        c + h * Vec3A::new(1.0, -1.0, 1.0),
+        c + h * Vec3A::new(-1.0, 1.0, 1.0),
+        c + h * Vec3A::new(1.0, 1.0, 1.0),
+    ]
+}
+
+/// The box around `aabb` after it has been moved by `matrix`.
+pub fn transform_aabb(aabb: &Aabb, matrix: &Mat4) -> Aabb {
+    let mut builder = BoundsBuilder::default();
+    for corner in aabb_corners(aabb) {
+        builder.add_point(matrix.transform_point3a(corner));
+    }
+    builder.build().unwrap()
+}
+
+/// The box around every vertex of `mesh` after it has been moved by `matrix`, `None` for a mesh
+/// without positions.
+pub fn mesh_vertex_bounds(mesh: &Mesh, matrix: &Mat4) -> Option<Aabb> {
+    let mut builder = BoundsBuilder::default();
+    for position in mesh_positions(mesh)? {
+        builder.add_point(matrix.transform_point3a(Vec3A::from(*position)));
+    }
+    builder.build()
+}
+
+/// The box around every vertex of a skinned `mesh`, blending the joint matrices by the vertex
+/// weights. `joint_matrices` map from mesh space to the target space, joint transform times
+/// inverse bind pose.
+pub fn skinned_vertex_bounds(mesh: &Mesh, joint_matrices: &[Mat4]) -> Option<Aabb> {
+    let positions = mesh_positions(mesh)?;
+    let Some(VertexAttributeValues::Uint16x4(joints)) = mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX)
+    else {
+        return None;
+    };
+    let Some(VertexAttributeValues::Float32x4(weights)) =
+        mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT)
+    else {
+        return None;
+    };
+
+    let mut builder = BoundsBuilder::default();
+    for ((position, joints), weights) in positions.iter().zip(joints).zip(weights) {
+        let mut skin = Mat4::ZERO;
+        for (joint, weight) in joints.iter().zip(weights) {
+            if let Some(joint_matrix) = joint_matrices.get(*joint as usize) {
+                skin += *joint_matrix * *weight;
+            }
+        }
+        builder.add_point(skin.transform_point3a(Vec3A::from(*position)));
+    }
+    builder.build()
+}
+
+fn mesh_positions(mesh: &Mesh) -> Option<&Vec<[f32; 3]>> {
+    match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => Some(positions),
+        _ => None,
+    }
+}
+
+/// What is known about the bounds of one mesh entity.
+#[derive(Debug, Clone, Copy)]
+pub enum MeshBoundsState {
+    Ready(Aabb),
+    /// The mesh asset or its `Aabb` isn't there yet, try again next frame.
+    Pending,
+    /// The mesh has no positions to bound.
+    Empty,
+}
+
+type MeshQuery = (
+    &'static GlobalTransform,
+    &'static Handle<Mesh>,
+    Option<&'static Aabb>,
+    Option<&'static SkinnedMesh>,
+    Option<&'static NoFrustumCulling>,
+);
+
+/// Everything needed to work out the bounds of meshes in the world.
+#[derive(SystemParam)]
+pub struct MeshBounds<'w, 's> {
+    meshes: Query<'w, 's, MeshQuery>,
+    joints: Query<'w, 's, &'static GlobalTransform>,
+    mesh_assets: Res<'w, Assets<Mesh>>,
+    inverse_bindposes: Res<'w, Assets<SkinnedMeshInverseBindposes>>,
+}
+
+impl<'w, 's> MeshBounds<'w, 's> {
+    /// Bounds of a mesh entity, moved into the space `to_space` maps world space to.
+    pub fn mesh_bounds(
+        &self,
+        entity: Entity,
+        to_space: &Mat4,
+        mode: BoundsMode,
+    ) -> MeshBoundsState {
+        let Ok((transform, mesh_handle, maybe_aabb, maybe_skinned, no_culling)) =
+            self.meshes.get(entity)
+        else {
+            return MeshBoundsState::Empty;
+        };
+        let Some(mesh) = self.mesh_assets.get(mesh_handle) else {
+            return MeshBoundsState::Pending;
+        };
+        // The Aabb is added after the first transform propagation, so it also tells us the
+        // GlobalTransform is up to date. Meshes that opt out of culling never get one.
+        let local_aabb = match maybe_aabb {
+            Some(aabb) => *aabb,
+            None if no_culling.is_some() => match mesh.compute_aabb() {
+                Some(aabb) => aabb,
+                None => return MeshBoundsState::Empty,
+            },
+            None if mesh.compute_aabb().is_none() => return MeshBoundsState::Empty,
+            None => return MeshBoundsState::Pending,
+        };
+
+        if let Some(skinned) = maybe_skinned {
+            let Some(joint_matrices) = self.joint_matrices(skinned, to_space) else {
+                return MeshBoundsState::Pending;
+            };
+            let bounds = match mode {
+                BoundsMode::Vertices => skinned_vertex_bounds(mesh, &joint_matrices),
+                // each vertex ends up somewhere between its joints' placements of the bind pose
+                BoundsMode::Corners => {
+                    let mut builder = BoundsBuilder::default();
+                    for joint_matrix in &joint_matrices {
+                        builder.add_aabb(&transform_aabb(&local_aabb, joint_matrix));
+                    }
+                    builder.build()
+                }
+            };
+            return bounds.map_or(MeshBoundsState::Empty, MeshBoundsState::Ready);
+        }
+
+        let matrix = *to_space * transform.compute_matrix();
+        let bounds = match mode {
+            BoundsMode::Vertices => mesh_vertex_bounds(mesh, &matrix),
+            BoundsMode::Corners => Some(transform_aabb(&local_aabb, &matrix)),
+        };
+        bounds.map_or(MeshBoundsState::Empty, MeshBoundsState::Ready)
+    }
+
+    fn joint_matrices(&self, skinned: &SkinnedMesh, to_space: &Mat4) -> Option<Vec<Mat4>> {
+        let inverse_bindposes = self.inverse_bindposes.get(&skinned.inverse_bindposes)?;
+        skinned
+            .joints
+            .iter()
+            .zip(inverse_bindposes.iter())
+            .map(|(joint, inverse_bindpose)| {
+                let joint = self.joints.get(*joint).ok()?;
+                Some(*to_space * joint.compute_matrix() * *inverse_bindpose)
+            })
+            .collect()
+    }
+
+    /// Bounds of every mesh in a spawned scene instance, moved into the space `to_space` maps world
+    /// space to. `None` while any of them is still pending, unless `skip_pending` leaves those out
+    /// as if they were empty; a scene without meshes gets an empty box at the origin.
+    pub fn instance_bounds(
+        &self,
+        scene_spawner: &SceneSpawner,
+        instance_id: InstanceId,
+        to_space: &Mat4,
+        mode: BoundsMode,
+        skip_pending: bool,
+    ) -> Option<Aabb> {
+        let mut builder = BoundsBuilder::default();
+        for entity in scene_spawner.iter_instance_entities(instance_id) {
+            match self.mesh_bounds(entity, to_space, mode) {
+                MeshBoundsState::Ready(aabb) => builder.add_aabb(&aabb),
+                MeshBoundsState::Pending if !skip_pending => return None,
+                MeshBoundsState::Pending | MeshBoundsState::Empty => {}
+            }
+        }
+        Some(
+            builder
+                .build()
+                .unwrap_or_else(|| Aabb::from_min_max(Vec3::ZERO, Vec3::ZERO)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use bevy::{ecs::system::SystemState, render::render_resource::PrimitiveTopology};
+
+    use super::*;
+
+    fn assert_bounds(aabb: &Aabb, min: Vec3, max: Vec3) {
+        assert!(
+            Vec3::from(aabb.min()).distance(min) < 1e-5
+                && Vec3::from(aabb.max()).distance(max) < 1e-5,
+            "got {:?} to {:?}, expected {:?} to {:?}",
+            aabb.min(),
+            aabb.max(),
+            min,
+            max
+        );
+    }
+
+    /// Spans (0, -2, -3) to (2, 2, 3).
+    fn known_aabb() -> Aabb {
+        Aabb::from_min_max(Vec3::new(0.0, -2.0, -3.0), Vec3::new(2.0, 2.0, 3.0))
+    }
+
+    #[test]
+    fn rotated_aabb() {
+        // a quarter turn around Y takes (x, y, z) to (z, y, -x)
+        let matrix = Mat4::from_rotation_y(FRAC_PI_2);
+        assert_bounds(
+            &transform_aabb(&known_aabb(), &matrix),
+            Vec3::new(-3.0, -2.0, -2.0),
+            Vec3::new(3.0, 2.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn scaled_aabb() {
+        let matrix = Mat4::from_scale(Vec3::new(2.0, 1.0, 0.5));
+        assert_bounds(
+            &transform_aabb(&known_aabb(), &matrix),
+            Vec3::new(0.0, -2.0, -1.5),
+            Vec3::new(4.0, 2.0, 1.5),
+        );
+    }
+
+    #[test]
+    fn scaled_rotated_and_moved_aabb() {
+        let matrix = Mat4::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 0.5),
+            Quat::from_rotation_y(FRAC_PI_2),
+            Vec3::new(0.0, 10.0, 0.0),
+        );
+        // scaled to (0, -2, -1.5)..(4, 2, 1.5), turned to (-1.5, -2, -4)..(1.5, 2, 0), then moved up
+        assert_bounds(
+            &transform_aabb(&known_aabb(), &matrix),
+            Vec3::new(-1.5, 8.0, -4.0),
+            Vec3::new(1.5, 12.0, 0.0),
+        );
+    }
+
+    fn two_vertex_mesh() -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::PointList);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[1.0f32, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        );
+        mesh
+    }
+
+    #[test]
+    fn vertex_bounds() {
+        let matrix = Mat4::from_translation(Vec3::new(0.0, 0.0, 5.0));
+        assert_bounds(
+            &mesh_vertex_bounds(&two_vertex_mesh(), &matrix).unwrap(),
+            Vec3::new(0.0, 0.0, 5.0),
+            Vec3::new(1.0, 1.0, 5.0),
+        );
+    }
+
+    #[test]
+    fn two_joint_skin_blend() {
+        let mut mesh = two_vertex_mesh();
+        // the first vertex is shared evenly by both joints, the second follows the second joint
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_JOINT_INDEX,
+            VertexAttributeValues::Uint16x4(vec![[0, 1, 0, 0], [1, 0, 0, 0]]),
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_JOINT_WEIGHT,
+            vec![[0.5f32, 0.5, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0]],
+        );
+        let joint_matrices = [
+            Mat4::IDENTITY,
+            // a quarter turn around Z, then two units along X
+            Mat4::from_rotation_translation(
+                Quat::from_rotation_z(FRAC_PI_2),
+                Vec3::new(2.0, 0.0, 0.0),
+            ),
+        ];
+
+        // (1, 0, 0) is halfway between (1, 0, 0) and (2, 1, 0), (0, 1, 0) goes to (1, 0, 0)
+        assert_bounds(
+            &skinned_vertex_bounds(&mesh, &joint_matrices).unwrap(),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(1.5, 0.5, 0.0),
+        );
+    }
+
+    #[test]
+    fn skin_without_joints_has_no_bounds() {
+        assert!(skinned_vertex_bounds(&two_vertex_mesh(), &[Mat4::IDENTITY]).is_none());
+    }
+
+    #[test]
+    fn mesh_entity_bounds() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .add_asset::<SkinnedMeshInverseBindposes>();
+        let world = &mut app.world;
+        let mesh = two_vertex_mesh();
+        let local_aabb = mesh.compute_aabb().unwrap();
+        let mesh_handle = world.resource_mut::<Assets<Mesh>>().add(mesh);
+
+        let transform = Transform::from_translation(Vec3::new(0.0, 0.0, 5.0))
+            .with_rotation(Quat::from_rotation_y(FRAC_PI_2))
+            .with_scale(Vec3::splat(2.0));
+        let entity = world
+            .spawn((GlobalTransform::from(transform), mesh_handle, local_aabb))
+            .id();
+        let no_mesh = world.spawn(GlobalTransform::default()).id();
+
+        let mut state = SystemState::<MeshBounds>::new(world);
+        let mesh_bounds = state.get(world);
+
+        // (1, 0, 0) doubles and turns to (0, 0, -2), (0, 1, 0) doubles to (0, 2, 0)
+        let expected = (Vec3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 2.0, 5.0));
+        for mode in [BoundsMode::Corners, BoundsMode::Vertices] {
+            match mesh_bounds.mesh_bounds(entity, &Mat4::IDENTITY, mode) {
+                MeshBoundsState::Ready(aabb) => assert_bounds(&aabb, expected.0, expected.1),
+                state => panic!("expected bounds, got {:?}", state),
+            }
+        }
+        // the same bounds seen from a space moved five units back
+        match mesh_bounds.mesh_bounds(
+            entity,
+            &Mat4::from_translation(Vec3::new(0.0, 0.0, -5.0)),
+            BoundsMode::Corners,
+        ) {
+            MeshBoundsState::Ready(aabb) => {
+                assert_bounds(&aabb, Vec3::new(0.0, 0.0, -2.0), Vec3::new(0.0, 2.0, 0.0))
+            }
+            state => panic!("expected bounds, got {:?}", state),
+        }
+        assert!(matches!(
+            mesh_bounds.mesh_bounds(no_mesh, &Mat4::IDENTITY, BoundsMode::Corners),
+            MeshBoundsState::Empty
+        ));
+    }
+}
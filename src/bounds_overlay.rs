@@ -0,0 +1,160 @@
+//! Wireframe boxes around the whole scene and around every mesh, with their sizes in world units.
+
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+
+use crate::args::ViewerArgs;
+use crate::bounds::{MeshBounds, MeshBoundsState, SceneBounds};
+use crate::lines::LineList;
+use crate::scene_setup::SceneHandle;
+use crate::thick_lines::{LineWidth, ThickLineMaterial, ThickLines};
+use crate::world_label::{active_camera, world_label};
+
+const SCENE_COLOR: Color = Color::YELLOW;
+/// Mesh boxes are colored by the model they belong to.
+const MODEL_COLORS: [Color; 6] = [
+    Color::CYAN,
+    Color::ORANGE,
+    Color::LIME_GREEN,
+    Color::FUCHSIA,
+    Color::ALICE_BLUE,
+    Color::SALMON,
+];
+
+#[derive(Resource, Default)]
+pub struct BoundsOverlay {
+    pub enabled: bool,
+    /// Where to print each box's size, and the size itself.
+    labels: Vec<(Vec3, String)>,
+}
+
+#[derive(Component)]
+pub struct BoundsOverlayLines;
+
+pub fn toggle_bounds_overlay(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut bounds_overlay: ResMut<BoundsOverlay>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::O) {
+        bounds_overlay.enabled = !bounds_overlay.enabled;
+    }
+}
+
+/// Rebuilds the boxes when the overlay is toggled or the scene changes.
+#[allow(clippy::too_many_arguments)]
+pub fn update_bounds_overlay(
+    mut commands: Commands,
+    mut bounds_overlay: ResMut<BoundsOverlay>,
+    scene_bounds: Res<SceneBounds>,
+    viewer_args: Res<ViewerArgs>,
+    scene_spawner: Res<SceneSpawner>,
+    scene_handles: Query<(&SceneHandle, &Transform, &GlobalTransform)>,
+    mesh_bounds: MeshBounds,
+    overlay_lines: Query<Entity, With<BoundsOverlayLines>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ThickLineMaterial>>,
+) {
+    if !bounds_overlay.is_changed() && !scene_bounds.is_changed() {
+        return;
+    }
+
+    for entity in &overlay_lines {
+        commands.entity(entity).despawn_recursive();
+    }
+    // the labels are derived data, changing them shouldn't trigger another rebuild
+    let bounds_overlay = bounds_overlay.bypass_change_detection();
+    bounds_overlay.labels.clear();
+
+    let Some(scene_aabb) = scene_bounds.aabb() else {
+        return;
+    };
+    if !bounds_overlay.enabled {
+        return;
+    }
+
+    let mut spawn_boxes = |aabbs: &[Aabb], material: ThickLineMaterial| {
+        commands.spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(Mesh::from(ThickLines(LineList::boxes(aabbs)))),
+                material: materials.add(material),
+                ..default()
+            },
+            BoundsOverlayLines,
+        ));
+    };
+
+    // the scene box is dashed and drawn over the models so it can be told apart where it
+    // touches a mesh box
+    let dash = 0.02 * scene_aabb.half_extents.max_element();
+    spawn_boxes(
+        &[*scene_aabb],
+        ThickLineMaterial {
+            color: SCENE_COLOR,
+            width: LineWidth::Pixels(2.0),
+            dashes: Some((dash, dash)),
+            depth_test: false,
+        },
+    );
+    bounds_overlay.labels.push(size_label(scene_aabb));
+
+    for (index, (scene_handle, transform, root)) in scene_handles.iter().enumerate() {
+        let Some(instance_id) = scene_handle.instance_id() else {
+            continue;
+        };
+        // go through the root's Transform rather than the meshes' GlobalTransform, which lags a
+        // frame behind when the grid has just moved the model
+        let to_world = transform.compute_matrix() * root.compute_matrix().inverse();
+
+        let aabbs: Vec<Aabb> = scene_spawner
+            .iter_instance_entities(instance_id)
+            .filter_map(|entity| {
+                match mesh_bounds.mesh_bounds(entity, &to_world, viewer_args.bounds_mode) {
+                    MeshBoundsState::Ready(aabb) => Some(aabb),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        bounds_overlay.labels.extend(aabbs.iter().map(size_label));
+        spawn_boxes(
+            &aabbs,
+            ThickLineMaterial {
+                color: MODEL_COLORS[index % MODEL_COLORS.len()],
+                width: LineWidth::Pixels(1.5),
+                ..default()
+            },
+        );
+    }
+}
+
+/// The top center of the box and its width x height x depth.
+fn size_label(aabb: &Aabb) -> (Vec3, String) {
+    let size = 2.0 * aabb.half_extents;
+    (
+        Vec3::from(aabb.center) + Vec3::Y * aabb.half_extents.y,
+        format!("{:.2} x {:.2} x {:.2}", size.x, size.y, size.z),
+    )
+}
+
+pub fn show_bounds_labels(
+    mut contexts: EguiContexts,
+    bounds_overlay: Res<BoundsOverlay>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    if !bounds_overlay.enabled {
+        return;
+    }
+    let Some(camera) = active_camera(&cameras) else {
+        return;
+    };
+    let ctx = contexts.ctx_mut();
+
+    for (index, (position, label)) in bounds_overlay.labels.iter().enumerate() {
+        world_label(ctx, ("bounds_label", index), camera, *position, label);
+    }
+}
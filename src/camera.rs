@@ -6,6 +6,8 @@ use bevy::{
     prelude::*,
 };
 
+use crate::camera_pan_orbit::{CameraFraming, DEFAULT_VIEW_DIRECTION, rotation_from_direction};
+
 #[derive(Resource, Default)]
 pub struct CameraTracker {
     active_index: Option<usize>,
@@ -35,6 +37,7 @@ impl CameraTracker {
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn camera_tracker(
     mut camera_tracker: ResMut<CameraTracker>,
     keyboard_input: Res<Input<KeyCode>>,
@@ -224,3 +227,19 @@ pub fn camera_controller(
         }
     }
 }
+
+pub fn spawn_fly_camera(commands: &mut Commands, framing: &CameraFraming) -> Entity {
+    info!("Spawning a flying 3D perspective camera");
+
+    commands.spawn((
+        Camera3dBundle {
+            transform: framing.transform(rotation_from_direction(DEFAULT_VIEW_DIRECTION)),
+            projection: framing.projection().into(),
+            ..Default::default()
+        },
+        CameraController {
+            target: framing.focus,
+            ..Default::default()
+        },
+    )).id()
+}
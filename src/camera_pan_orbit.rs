@@ -3,6 +3,10 @@ use bevy::prelude::*;
 use bevy::input::mouse::{MouseWheel,MouseMotion};
 use bevy::render::camera::Projection;
 use bevy::window::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+
+use crate::camera::CameraController;
+use crate::bounds::SceneBounds;
 
 // ANCHOR: example
 /// Tags an entity as capable of panning and orbiting.
@@ -88,7 +92,7 @@ pub fn pan_orbit_camera(
             let pitch = Quat::from_rotation_x(-delta_y);
 
             transform.rotation = yaw * transform.rotation; // rotate around global y axis
-            transform.rotation = transform.rotation * pitch; // rotate around local x axis
+            transform.rotation *= pitch; // rotate around local x axis
 
         } else if pan.length_squared() > 0.0 {
             any = true;
@@ -128,21 +132,214 @@ pub fn pan_orbit_camera(
     }
 }
 
-pub fn spawn_camera(commands: &mut Commands) {
-    info!("Spawning a controllable 3D perspective camera");
+/// The direction the camera looks at the scene from when it is first framed, a three-quarter view
+/// from the front.
+pub const DEFAULT_VIEW_DIRECTION: Vec3 = Vec3::new(-2.0, 2.5, 5.0);
+
+/// Smallest bounding sphere radius that is framed, so empty or flat scenes still get a usable view.
+const MIN_FRAMED_RADIUS: f32 = 0.01;
+
+/// Where to put an orbiting camera so a bounding box fills the view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraFraming {
+    pub focus: Vec3,
+    /// Distance from the focus to the camera.
+    pub radius: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl CameraFraming {
+    /// The camera transform for this framing, looking at the focus with the given rotation.
+    pub fn transform(&self, rotation: Quat) -> Transform {
+        Transform {
+            translation: self.focus + rotation * Vec3::new(0.0, 0.0, self.radius),
+            rotation,
+            ..default()
+        }
+    }
+
+    pub fn projection(&self) -> PerspectiveProjection {
+        PerspectiveProjection {
+            near: self.near,
+            far: self.far,
+            ..default()
+        }
+    }
+}
+
+/// Frames the box given by `center` and `half_extents` for a perspective camera with the vertical
+/// field of view `fov_y` (radians) and `aspect_ratio` (width / height).
+///
+/// The camera is pulled back until the box's bounding sphere fits the narrower of the two fields of
+/// view, so the whole box stays in view from any direction.
+pub fn frame_bounds(
+    center: Vec3,
+    half_extents: Vec3,
+    fov_y: f32,
+    aspect_ratio: f32,
+) -> CameraFraming {
+    let sphere_radius = half_extents.length().max(MIN_FRAMED_RADIUS);
+    let fov_x = 2.0 * ((fov_y / 2.0).tan() * aspect_ratio).atan();
+    let half_fov = fov_y.min(fov_x) / 2.0;
+    let radius = sphere_radius / half_fov.sin();
 
-    let translation = Vec3::new(-2.0, 2.5, 5.0);
-    let radius = translation.length();
+    CameraFraming {
+        focus: center,
+        radius,
+        near: (radius * 0.1).min(0.1),
+        // leave room to zoom out
+        far: (radius + sphere_radius) * 10.0,
+    }
+}
+
+/// The rotation of a camera looking at its focus from `direction`.
+pub fn rotation_from_direction(direction: Vec3) -> Quat {
+    Transform::from_translation(direction)
+        .looking_at(Vec3::ZERO, Vec3::Y)
+        .rotation
+}
+
+pub fn spawn_camera(commands: &mut Commands, framing: &CameraFraming) -> Entity {
+    info!("Spawning a controllable 3D perspective camera");
 
     commands.spawn((
         Camera3dBundle {
-            transform: Transform::from_translation(translation)
-                .looking_at(Vec3::ZERO, Vec3::Y),
+            transform: framing.transform(rotation_from_direction(DEFAULT_VIEW_DIRECTION)),
+            projection: framing.projection().into(),
             ..Default::default()
         },
         PanOrbitCamera {
-            radius,
+            focus: framing.focus,
+            radius: framing.radius,
             ..Default::default()
         },
-    ));
+    )).id()
+}
+
+/// Re-frames the viewer's own cameras on the scene bounds when F is pressed, keeping their current
+/// direction. Gizmo, screenshot and glTF cameras are left alone.
+#[allow(clippy::type_complexity)]
+pub fn frame_scene(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    scene_bounds: Res<SceneBounds>,
+    mut cameras: Query<
+        (
+            &mut Transform,
+            &mut Projection,
+            Option<&mut PanOrbitCamera>,
+            Option<&mut CameraController>,
+        ),
+        Or<(With<PanOrbitCamera>, With<CameraController>)>,
+    >,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() || !keyboard_input.just_pressed(KeyCode::F) {
+        return;
+    }
+    let Some(aabb) = scene_bounds.aabb() else {
+        return;
+    };
+
+    for (mut transform, mut projection, pan_orbit, camera_controller) in &mut cameras {
+        let Projection::Perspective(perspective) = &mut *projection else {
+            continue;
+        };
+        let framing = frame_bounds(
+            Vec3::from(aabb.center),
+            Vec3::from(aabb.half_extents),
+            perspective.fov,
+            perspective.aspect_ratio,
+        );
+        perspective.near = framing.near;
+        perspective.far = framing.far;
+        *transform = framing.transform(transform.rotation);
+
+        if let Some(mut pan_orbit) = pan_orbit {
+            pan_orbit.focus = framing.focus;
+            pan_orbit.radius = framing.radius;
+        }
+        if let Some(mut camera_controller) = camera_controller {
+            camera_controller.target = framing.focus;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOV_45: f32 = std::f32::consts::FRAC_PI_4;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() <= 1e-5 * expected.abs().max(1.0),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn unit_cube() {
+        let framing = frame_bounds(Vec3::new(1.0, 2.0, 3.0), Vec3::splat(0.5), FOV_45, 1.0);
+        let sphere_radius = 0.75f32.sqrt();
+        let radius = sphere_radius / (FOV_45 / 2.0).sin();
+
+        assert_eq!(framing.focus, Vec3::new(1.0, 2.0, 3.0));
+        assert_near(framing.radius, radius);
+        assert_near(framing.near, 0.1);
+        assert_near(framing.far, (radius + sphere_radius) * 10.0);
+    }
+
+    #[test]
+    fn wide_aspect_uses_the_vertical_fov() {
+        let square = frame_bounds(Vec3::ZERO, Vec3::splat(0.5), FOV_45, 1.0);
+        let wide = frame_bounds(Vec3::ZERO, Vec3::splat(0.5), FOV_45, 16.0 / 9.0);
+        assert_near(wide.radius, square.radius);
+    }
+
+    #[test]
+    fn portrait_aspect_uses_the_horizontal_fov() {
+        let aspect_ratio = 0.5;
+        let framing = frame_bounds(Vec3::ZERO, Vec3::splat(0.5), FOV_45, aspect_ratio);
+        let half_fov_x = ((FOV_45 / 2.0).tan() * aspect_ratio).atan();
+
+        assert_near(framing.radius, 0.75f32.sqrt() / half_fov_x.sin());
+        assert!(framing.radius > frame_bounds(Vec3::ZERO, Vec3::splat(0.5), FOV_45, 1.0).radius);
+    }
+
+    #[test]
+    fn zero_size_bounds() {
+        let framing = frame_bounds(Vec3::ONE, Vec3::ZERO, FOV_45, 1.0);
+        let radius = MIN_FRAMED_RADIUS / (FOV_45 / 2.0).sin();
+
+        assert_eq!(framing.focus, Vec3::ONE);
+        assert_near(framing.radius, radius);
+        assert_near(framing.near, radius * 0.1);
+        assert_near(framing.far, (radius + MIN_FRAMED_RADIUS) * 10.0);
+        assert!(framing.near > 0.0 && framing.near < framing.far);
+    }
+
+    #[test]
+    fn near_is_clamped_to_a_tenth() {
+        // a camera further than a unit away keeps the near plane at 0.1
+        let large = frame_bounds(Vec3::ZERO, Vec3::splat(100.0), FOV_45, 1.0);
+        assert_near(large.near, 0.1);
+
+        // closer than that it is a tenth of the distance
+        let small = frame_bounds(Vec3::ZERO, Vec3::splat(0.01), FOV_45, 1.0);
+        assert!(small.radius < 1.0);
+        assert_near(small.near, small.radius * 0.1);
+    }
+
+    #[test]
+    fn transform_looks_at_the_focus() {
+        let framing = frame_bounds(Vec3::new(0.0, 1.0, 0.0), Vec3::ONE, FOV_45, 1.0);
+        let transform = framing.transform(rotation_from_direction(DEFAULT_VIEW_DIRECTION));
+
+        assert_near(transform.translation.distance(framing.focus), framing.radius);
+        let to_focus = (framing.focus - transform.translation).normalize();
+        assert!(transform.forward().distance(to_focus) < 1e-5);
+    }
 }
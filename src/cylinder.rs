@@ -1,11 +1,11 @@
 // from https://github.com/rparrett/typey_birb/blob/main/src/cylinder.rs
 
 use bevy::{
-    prelude::Mesh,
+    prelude::{Mesh, Vec3},
     render::{mesh::Indices, render_resource::PrimitiveTopology},
 };
 
-/// A cylinder which stands on the XZ plane
+/// A cylinder centered on the origin, standing up the Y axis
 pub struct Cylinder {
     /// Radius in the XZ plane.
     pub radius: f32,
@@ -15,6 +15,12 @@ pub struct Cylinder {
     pub resolution: u32,
     // Number of vertical segments.
     pub segments: u32,
+    /// Close the ends with flat caps. Without them the cylinder is an open tube.
+    pub caps: bool,
+    /// Share vertices along the UV seam and between the barrel and the caps, so the mesh is
+    /// closed, e.g. for physics or for counting volume. The edges around the caps are then
+    /// smoothed and the UVs wrap back on the last face.
+    pub watertight: bool,
 }
 
 impl Default for Cylinder {
@@ -24,6 +30,8 @@ impl Default for Cylinder {
             height: 1.0,
             resolution: 16,
             segments: 4,
+            caps: true,
+            watertight: false,
         }
     }
 }
@@ -36,9 +44,17 @@ impl From<Cylinder> for Mesh {
         debug_assert!(c.segments > 0);
 
         let num_rings = c.segments + 1;
-        let num_vertices = c.resolution * 2 + num_rings * (c.resolution + 1);
-        let num_faces = c.resolution * (num_rings - 2);
-        let num_indices = (2 * num_faces + 2 * (c.resolution - 1) * 2) * 3;
+        // the seam gets its own vertices for the UVs, unless the mesh is watertight
+        let ring_size = if c.watertight {
+            c.resolution
+        } else {
+            c.resolution + 1
+        };
+        let num_caps = if c.caps { 2 } else { 0 };
+        // a watertight cap only adds its center, the rim is the barrel's end ring
+        let cap_vertices = if c.watertight { 1 } else { c.resolution + 1 };
+        let num_vertices = num_rings * ring_size + num_caps * cap_vertices;
+        let num_indices = (c.segments * c.resolution * 2 + num_caps * c.resolution) * 3;
 
         let mut positions = Vec::with_capacity(num_vertices as usize);
         let mut normals = Vec::with_capacity(num_vertices as usize);
@@ -47,18 +63,32 @@ impl From<Cylinder> for Mesh {
 
         let step_theta = std::f32::consts::TAU / c.resolution as f32;
         let step_y = c.height / c.segments as f32;
+        let next = |j: u32| {
+            if c.watertight {
+                (j + 1) % c.resolution
+            } else {
+                j + 1
+            }
+        };
 
         // rings
 
         for ring in 0..num_rings {
             let y = -c.height / 2.0 + ring as f32 * step_y;
+            // shared with a cap, so the normal leans halfway towards it
+            let normal_y = match ring {
+                0 if c.watertight && c.caps => -1.0,
+                _ if ring == c.segments && c.watertight && c.caps => 1.0,
+                _ => 0.0,
+            };
 
-            for segment in 0..=c.resolution {
+            for segment in 0..ring_size {
                 let theta = segment as f32 * step_theta;
                 let (sin, cos) = theta.sin_cos();
+                let normal = Vec3::new(cos, normal_y, sin).normalize();
 
                 positions.push([c.radius * cos, y, c.radius * sin]);
-                normals.push([cos, 0., sin]);
+                normals.push(normal.to_array());
                 uvs.push([
                     segment as f32 / c.resolution as f32,
                     ring as f32 / c.segments as f32,
@@ -69,53 +99,71 @@ impl From<Cylinder> for Mesh {
         // barrel skin
 
         for i in 0..c.segments {
-            let ring = i * (c.resolution + 1);
-            let next_ring = (i + 1) * (c.resolution + 1);
+            let ring = i * ring_size;
+            let next_ring = (i + 1) * ring_size;
 
             for j in 0..c.resolution {
                 indices.extend_from_slice(&[
                     ring + j,
                     next_ring + j,
-                    ring + j + 1,
+                    ring + next(j),
                     next_ring + j,
-                    next_ring + j + 1,
-                    ring + j + 1,
+                    next_ring + next(j),
+                    ring + next(j),
                 ]);
             }
         }
 
-        // caps
+        // caps, a fan around a center vertex
 
         let mut build_cap = |top: bool| {
-            let offset = positions.len() as u32;
-            let (y, normal_y, winding) = if top {
-                (c.height / 2., 1., (1, 0))
+            let center = positions.len() as u32;
+            let (y, normal_y) = if top {
+                (c.height / 2., 1.)
             } else {
-                (c.height / -2., -1., (0, 1))
+                (c.height / -2., -1.)
             };
 
-            for i in 0..c.resolution {
-                let theta = i as f32 * step_theta;
-                let (sin, cos) = theta.sin_cos();
+            positions.push([0.0, y, 0.0]);
+            normals.push([0.0, normal_y, 0.0]);
+            uvs.push([0.5, 0.5]);
 
-                positions.push([cos * c.radius, y, sin * c.radius]);
-                normals.push([0.0, normal_y, 0.0]);
-                uvs.push([0.5 * (cos + 1.0), 1.0 - 0.5 * (sin + 1.0)]);
-            }
+            let rim = if c.watertight {
+                if top {
+                    c.segments * ring_size
+                } else {
+                    0
+                }
+            } else {
+                for i in 0..c.resolution {
+                    let theta = i as f32 * step_theta;
+                    let (sin, cos) = theta.sin_cos();
 
-            for i in 1..(c.resolution as u32 - 1) {
-                indices.extend_from_slice(&[
-                    offset,
-                    offset + i + winding.0,
-                    offset + i + winding.1,
-                ]);
+                    positions.push([cos * c.radius, y, sin * c.radius]);
+                    normals.push([0.0, normal_y, 0.0]);
+                    uvs.push([0.5 * (cos + 1.0), 1.0 - 0.5 * (sin + 1.0)]);
+                }
+                center + 1
+            };
+
+            for i in 0..c.resolution {
+                let (a, b) = (rim + i, rim + (i + 1) % c.resolution);
+                // counter-clockwise seen from outside the cylinder
+                if top {
+                    indices.extend_from_slice(&[center, b, a]);
+                } else {
+                    indices.extend_from_slice(&[center, a, b]);
+                }
             }
         };
 
-        // top
+        if c.caps {
+            build_cap(true);
+            build_cap(false);
+        }
 
-        build_cap(true);
-        build_cap(false);
+        debug_assert_eq!(positions.len(), num_vertices as usize);
+        debug_assert_eq!(indices.len(), num_indices as usize);
 
         let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
         mesh.set_indices(Some(Indices::U32(indices)));
@@ -125,3 +173,93 @@ impl From<Cylinder> for Mesh {
         mesh
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::shapes::tests::check_shape;
+
+    use super::*;
+
+    fn cylinder(caps: bool, watertight: bool) -> Cylinder {
+        Cylinder {
+            radius: 0.5,
+            height: 2.0,
+            resolution: 8,
+            segments: 3,
+            caps,
+            watertight,
+        }
+    }
+
+    /// How many triangles share each edge, whichever way round they use it.
+    fn edge_counts(triangles: &[[usize; 3]]) -> HashMap<(usize, usize), u32> {
+        let mut counts = HashMap::new();
+        for &[a, b, c] in triangles {
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                *counts.entry((from.min(to), from.max(to))).or_default() += 1;
+            }
+        }
+        counts
+    }
+
+    /// `check_shape` also checks the indices, normals and winding of each combination.
+    #[test]
+    fn counts() {
+        // (caps, watertight, vertices, triangles) for 8 vertices around and 3 segments up
+        for (caps, watertight, vertices, triangles) in [
+            (true, false, 4 * 9 + 2 * 9, 3 * 8 * 2 + 2 * 8),
+            (false, false, 4 * 9, 3 * 8 * 2),
+            (true, true, 4 * 8 + 2, 3 * 8 * 2 + 2 * 8),
+            (false, true, 4 * 8, 3 * 8 * 2),
+        ] {
+            let (positions, mesh_triangles) = check_shape(&Mesh::from(cylinder(caps, watertight)));
+            assert_eq!(
+                (positions.len(), mesh_triangles.len()),
+                (vertices, triangles),
+                "caps: {}, watertight: {}",
+                caps,
+                watertight
+            );
+        }
+    }
+
+    #[test]
+    fn extents() {
+        let (positions, _) = check_shape(&Mesh::from(cylinder(true, false)));
+        for p in positions {
+            assert!(p.y.abs() <= 1.0 + 1e-5);
+            assert!(Vec3::new(p.x, 0.0, p.z).length() <= 0.5 + 1e-5);
+        }
+    }
+
+    #[test]
+    fn watertight_is_closed() {
+        let (_, triangles) = check_shape(&Mesh::from(cylinder(true, true)));
+        for (edge, count) in edge_counts(&triangles) {
+            assert_eq!(count, 2, "edge {:?}", edge);
+        }
+    }
+
+    #[test]
+    fn open_ended_is_only_open_at_the_rims() {
+        let (positions, triangles) = check_shape(&Mesh::from(cylinder(false, true)));
+        let mut boundary = 0;
+        for ((a, b), count) in edge_counts(&triangles) {
+            if count == 1 {
+                let (y, other_y) = (positions[a].y, positions[b].y);
+                assert!(
+                    (y.abs() - 1.0).abs() < 1e-5 && (other_y - y).abs() < 1e-5,
+                    "edge {:?} is open",
+                    (a, b)
+                );
+                boundary += 1;
+            } else {
+                assert_eq!(count, 2, "edge {:?}", (a, b));
+            }
+        }
+        // every edge around both rims
+        assert_eq!(boundary, 2 * 8);
+    }
+}
@@ -0,0 +1,227 @@
+//! A small GIF encoder for looping animations.
+//!
+//! Every frame shares one palette learned from all of them, so colors don't flicker from frame to
+//! frame. Frames are stored whole, without any delta between them.
+
+use color_quant::NeuQuant;
+
+/// NeuQuant sampling factor, 1 learns from every pixel and 30 is the fastest.
+const SAMPLE_FACTOR: i32 = 10;
+
+/// Pixels with less alpha than this are transparent when the animation is.
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// Encodes RGBA `frames` of `width` x `height` pixels as a GIF that loops forever, showing each
+/// frame for `delay` hundredths of a second. With `transparent` the palette keeps its last entry
+/// for pixels with little alpha.
+pub fn encode_gif(
+    frames: &[Vec<u8>],
+    width: u16,
+    height: u16,
+    delay: u16,
+    transparent: bool,
+) -> Vec<u8> {
+    let transparent_index = transparent.then_some(255);
+    let mut opaque: Vec<u8> = frames
+        .iter()
+        .flat_map(|frame| frame.chunks_exact(4))
+        .filter(|pixel| transparent_index.is_none() || pixel[3] >= ALPHA_THRESHOLD)
+        .flatten()
+        .copied()
+        .collect();
+    // the quantizer needs something to learn from
+    if opaque.is_empty() {
+        opaque.extend_from_slice(&[0, 0, 0, 255]);
+    }
+    // it learns from one in SAMPLE_FACTOR pixels, so a tiny animation is repeated until there is a
+    // sample for every palette entry
+    let min_bytes = 256 * SAMPLE_FACTOR as usize * 4;
+    if opaque.len() < min_bytes {
+        opaque = opaque.repeat(min_bytes.div_ceil(opaque.len()));
+    }
+    let colors = if transparent { 255 } else { 256 };
+    let quantizer = NeuQuant::new(SAMPLE_FACTOR, colors, &opaque);
+    let mut palette = quantizer.color_map_rgb();
+    palette.resize(256 * 3, 0);
+
+    let mut gif = Vec::new();
+    gif.extend_from_slice(b"GIF89a");
+    gif.extend_from_slice(&width.to_le_bytes());
+    gif.extend_from_slice(&height.to_le_bytes());
+    // a global color table of 256 colors with 8 bits per channel, no background or aspect ratio
+    gif.extend_from_slice(&[0xF7, 0, 0]);
+    gif.extend_from_slice(&palette);
+    // the NETSCAPE2.0 extension, loop forever
+    gif.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+    gif.extend_from_slice(b"NETSCAPE2.0");
+    gif.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    for frame in frames {
+        let indices: Vec<u8> = frame
+            .chunks_exact(4)
+            .map(|pixel| match transparent_index {
+                Some(index) if pixel[3] < ALPHA_THRESHOLD => index,
+                _ => quantizer.index_of(pixel) as u8,
+            })
+            .collect();
+
+        // graphic control extension with the delay, a transparent frame is cleared before the next
+        // one is drawn so they don't pile up
+        let flags = match transparent_index {
+            Some(_) => (2 << 2) | 1,
+            None => 1 << 2,
+        };
+        gif.extend_from_slice(&[0x21, 0xF9, 0x04, flags]);
+        gif.extend_from_slice(&delay.to_le_bytes());
+        gif.extend_from_slice(&[transparent_index.unwrap_or(0), 0x00]);
+
+        // an image covering the whole canvas, using the global color table
+        gif.push(0x2C);
+        gif.extend_from_slice(&[0, 0, 0, 0]);
+        gif.extend_from_slice(&width.to_le_bytes());
+        gif.extend_from_slice(&height.to_le_bytes());
+        gif.push(0);
+
+        // LZW compressed indices, in sub-blocks of at most 255 bytes
+        gif.push(8);
+        let data = weezl::encode::Encoder::new(weezl::BitOrder::Lsb, 8)
+            .encode(&indices)
+            .expect("8 bit indices always encode");
+        for block in data.chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+
+    gif.push(0x3B);
+    gif
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const CLEAR: [u8; 4] = [0, 255, 0, 0];
+
+    /// A 2x2 red and blue checkerboard, then the same with a transparent corner.
+    fn frames() -> Vec<Vec<u8>> {
+        vec![
+            [RED, BLUE, BLUE, RED].concat(),
+            [RED, BLUE, BLUE, CLEAR].concat(),
+        ]
+    }
+
+    /// A frame's graphic control extension and its decoded indices.
+    struct Frame {
+        flags: u8,
+        delay: u16,
+        transparent_index: u8,
+        indices: Vec<u8>,
+    }
+
+    /// The frames after the header, palette and loop extension, checking the blocks around them.
+    fn read_frames(gif: &[u8]) -> Vec<Frame> {
+        let mut frames = Vec::new();
+        let mut at = 13 + 256 * 3 + 19;
+        loop {
+            match gif[at] {
+                0x21 => {
+                    assert_eq!(&gif[at + 1..at + 3], &[0xF9, 0x04]);
+                    let control = &gif[at + 3..at + 7];
+                    assert_eq!(gif[at + 7], 0, "block terminator");
+                    at += 8;
+
+                    assert_eq!(gif[at], 0x2C, "image descriptor");
+                    assert_eq!(&gif[at + 1..at + 10], &[0, 0, 0, 0, 2, 0, 2, 0, 0]);
+                    assert_eq!(gif[at + 10], 8, "LZW minimum code size");
+                    at += 11;
+                    let mut data = Vec::new();
+                    while gif[at] != 0 {
+                        let length = gif[at] as usize;
+                        data.extend_from_slice(&gif[at + 1..at + 1 + length]);
+                        at += 1 + length;
+                    }
+                    at += 1;
+
+                    frames.push(Frame {
+                        flags: control[0],
+                        delay: u16::from_le_bytes([control[1], control[2]]),
+                        transparent_index: control[3],
+                        indices: weezl::decode::Decoder::new(weezl::BitOrder::Lsb, 8)
+                            .decode(&data)
+                            .unwrap(),
+                    });
+                }
+                0x3B => {
+                    assert_eq!(at, gif.len() - 1, "trailer");
+                    return frames;
+                }
+                block => panic!("unexpected block {:#04X} at {}", block, at),
+            }
+        }
+    }
+
+    fn palette_color(gif: &[u8], index: u8) -> [u8; 3] {
+        let at = 13 + index as usize * 3;
+        [gif[at], gif[at + 1], gif[at + 2]]
+    }
+
+    #[test]
+    fn header_palette_and_loop() {
+        let gif = encode_gif(&frames(), 2, 2, 25, false);
+
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(&gif[6..10], &[2, 0, 2, 0]);
+        // a global color table of 2^(7 + 1) colors
+        assert_eq!(gif[10] & 0x80, 0x80);
+        assert_eq!(gif[10] & 0x07, 7);
+
+        let netscape = &gif[13 + 256 * 3..13 + 256 * 3 + 19];
+        assert_eq!(&netscape[..3], &[0x21, 0xFF, 0x0B]);
+        assert_eq!(&netscape[3..14], b"NETSCAPE2.0");
+        // loop forever
+        assert_eq!(&netscape[14..], &[0x03, 0x01, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn opaque_frames() {
+        let gif = encode_gif(&frames(), 2, 2, 25, false);
+        let frames = read_frames(&gif);
+
+        assert_eq!(frames.len(), 2);
+        for frame in &frames {
+            assert_eq!(frame.delay, 25);
+            // left in place, no transparency
+            assert_eq!(frame.flags, 1 << 2);
+            assert_eq!(frame.indices.len(), 4);
+        }
+        let [red, blue] = [frames[0].indices[0], frames[0].indices[1]];
+        assert_ne!(red, blue);
+        assert_eq!(frames[0].indices, [red, blue, blue, red]);
+        assert_eq!(palette_color(&gif, red), [255, 0, 0]);
+        assert_eq!(palette_color(&gif, blue), [0, 0, 255]);
+        // one palette for every frame, so the same colors keep their indices
+        assert_eq!(frames[1].indices[..3], [red, blue, blue]);
+    }
+
+    #[test]
+    fn transparent_frames() {
+        let gif = encode_gif(&frames(), 2, 2, 25, true);
+        let frames = read_frames(&gif);
+
+        assert_eq!(frames.len(), 2);
+        for frame in &frames {
+            // cleared before the next frame, with a transparent index
+            assert_eq!(frame.flags, (2 << 2) | 1);
+            assert_eq!(frame.transparent_index, 255);
+        }
+        let [red, blue] = [frames[0].indices[0], frames[0].indices[1]];
+        assert_eq!(frames[0].indices, [red, blue, blue, red]);
+        assert_eq!(frames[1].indices, [red, blue, blue, 255]);
+        assert_ne!(red, 255);
+        assert_ne!(blue, 255);
+    }
+}
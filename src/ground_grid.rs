@@ -0,0 +1,236 @@
+//! A grid of lines on the XZ plane, sized to the loaded models, as a reference for checking
+//! modular pieces against the unit tile. Lines sit on multiples of the spacing so they line up
+//! with the world origin, and fade out towards the far edges.
+
+use bevy::{
+    prelude::*,
+    reflect::TypeUuid,
+    render::render_resource::{AsBindGroup, ShaderRef},
+};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+
+use crate::bounds::SceneBounds;
+use crate::lines::LineList;
+use crate::world_label::active_camera;
+
+/// Every n-th line is drawn brighter.
+pub const MAJOR_LINE_EVERY: i32 = 10;
+/// The spacing is coarsened until there are no more lines than this along each axis.
+const MAX_LINES_PER_AXIS: f32 = 400.0;
+/// Half size of the grid before anything has loaded.
+const DEFAULT_HALF_SIZE: f32 = 5.0;
+
+const MINOR_COLOR: Color = Color::rgba(0.5, 0.5, 0.5, 0.35);
+const MAJOR_COLOR: Color = Color::rgba(0.8, 0.8, 0.8, 0.6);
+
+#[derive(Resource)]
+pub struct GroundGrid {
+    pub visible: bool,
+    /// Distance between minor lines, in world units.
+    pub spacing: f32,
+    /// Center and half size of the grid on the XZ plane, for fading.
+    center: Vec3,
+    half_size: f32,
+}
+
+impl GroundGrid {
+    pub fn new(visible: bool, spacing: f32) -> Self {
+        GroundGrid {
+            visible,
+            spacing,
+            center: Vec3::ZERO,
+            half_size: DEFAULT_HALF_SIZE,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct GroundGridLines;
+
+/// Lines on a material that fades with distance from the camera.
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "9d1a7c36-2f0e-4b0b-a5d1-6f4c1de2a8b3"]
+pub struct GridMaterial {
+    #[uniform(0)]
+    pub color: Color,
+    #[uniform(0)]
+    pub fade_start: f32,
+    #[uniform(0)]
+    pub fade_end: f32,
+}
+
+impl Material for GridMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "assets/shaders/grid_material.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+/// Minor and major lines covering `min` to `max` on the XZ plane, rounded out to whole major
+/// cells. The spacing is multiplied by `MAJOR_LINE_EVERY` until the line count is reasonable.
+pub fn grid_lines(min: Vec2, max: Vec2, spacing: f32) -> (LineList, LineList) {
+    let mut spacing = spacing;
+    while (max - min).max_element() / spacing > MAX_LINES_PER_AXIS {
+        spacing *= MAJOR_LINE_EVERY as f32;
+    }
+    let major = spacing * MAJOR_LINE_EVERY as f32;
+    let first = (min / major).floor() * major;
+    let last = (max / major).ceil() * major;
+
+    let mut minor_lines = Vec::new();
+    let mut major_lines = Vec::new();
+    for axis in 0..2 {
+        let start = (first[axis] / spacing).round() as i32;
+        let end = (last[axis] / spacing).round() as i32;
+        for i in start..=end {
+            let offset = i as f32 * spacing;
+            let line = if axis == 0 {
+                (
+                    Vec3::new(offset, 0.0, first.y),
+                    Vec3::new(offset, 0.0, last.y),
+                )
+            } else {
+                (
+                    Vec3::new(first.x, 0.0, offset),
+                    Vec3::new(last.x, 0.0, offset),
+                )
+            };
+            if i % MAJOR_LINE_EVERY == 0 {
+                major_lines.push(line);
+            } else {
+                minor_lines.push(line);
+            }
+        }
+    }
+
+    (
+        LineList {
+            lines: minor_lines,
+            ..default()
+        },
+        LineList {
+            lines: major_lines,
+            ..default()
+        },
+    )
+}
+
+pub fn toggle_ground_grid(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut ground_grid: ResMut<GroundGrid>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::G) {
+        ground_grid.visible = !ground_grid.visible;
+    }
+}
+
+/// Rebuilds the grid around the scene bounds whenever they change, and shows or hides it.
+pub fn update_ground_grid(
+    mut commands: Commands,
+    mut ground_grid: ResMut<GroundGrid>,
+    scene_bounds: Res<SceneBounds>,
+    mut grid_lines_query: Query<(Entity, &mut Visibility), With<GroundGridLines>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GridMaterial>>,
+) {
+    if ground_grid.is_changed() {
+        let visibility = if ground_grid.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        for (_, mut grid_visibility) in &mut grid_lines_query {
+            *grid_visibility = visibility;
+        }
+    }
+    if !scene_bounds.is_changed() {
+        return;
+    }
+
+    for (entity, _) in &grid_lines_query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // leave a margin of half the scene's size around it
+    let (center, half_size) = match scene_bounds.aabb() {
+        Some(aabb) => (
+            Vec2::new(aabb.center.x, aabb.center.z),
+            1.5 * aabb
+                .half_extents
+                .x
+                .max(aabb.half_extents.z)
+                .max(ground_grid.spacing),
+        ),
+        None => (Vec2::ZERO, DEFAULT_HALF_SIZE),
+    };
+    let (minor_lines, major_lines) = grid_lines(
+        center - Vec2::splat(half_size),
+        center + Vec2::splat(half_size),
+        ground_grid.spacing,
+    );
+    let ground_grid = ground_grid.bypass_change_detection();
+    ground_grid.center = Vec3::new(center.x, 0.0, center.y);
+    ground_grid.half_size = half_size;
+
+    let visibility = if ground_grid.visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for (lines, color) in [(minor_lines, MINOR_COLOR), (major_lines, MAJOR_COLOR)] {
+        if lines.lines.is_empty() {
+            continue;
+        }
+        commands.spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(Mesh::from(lines)),
+                material: materials.add(GridMaterial {
+                    color,
+                    // fade_ground_grid moves these with the camera
+                    fade_start: half_size,
+                    fade_end: 2.0 * half_size,
+                }),
+                visibility,
+                ..default()
+            },
+            GroundGridLines,
+        ));
+    }
+}
+
+/// Keeps the part of the grid near the camera solid and fades out the far side, however far the
+/// camera is from the scene.
+pub fn fade_ground_grid(
+    ground_grid: Res<GroundGrid>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    grid_lines_query: Query<&Handle<GridMaterial>, With<GroundGridLines>>,
+    mut materials: ResMut<Assets<GridMaterial>>,
+) {
+    let Some((_, camera_transform)) = active_camera(&cameras) else {
+        return;
+    };
+    let distance = camera_transform.translation().distance(ground_grid.center);
+    let fade_start = distance;
+    let fade_end = distance + ground_grid.half_size;
+
+    for handle in &grid_lines_query {
+        let Some(material) = materials.get(handle) else {
+            continue;
+        };
+        // only touch the asset when needed, every change uploads it again
+        if material.fade_start == fade_start && material.fade_end == fade_end {
+            continue;
+        }
+        if let Some(material) = materials.get_mut(handle) {
+            material.fade_start = fade_start;
+            material.fade_end = fade_end;
+        }
+    }
+}
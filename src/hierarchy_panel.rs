@@ -0,0 +1,208 @@
+//! A window with the entity tree of the loaded models, as spawned from the file, with the selected
+//! entity's transform and visibility editable and a summary of its mesh, material, bounds and
+//! lights.
+
+use bevy::{
+    asset::HandleId,
+    pbr::{DirectionalLight, PointLight, SpotLight},
+    prelude::*,
+    render::{mesh::skinning::SkinnedMesh, primitives::Aabb},
+    window::PrimaryWindow,
+};
+use bevy_inspector_egui::bevy_egui::{EguiContext, EguiContexts};
+use bevy_inspector_egui::bevy_inspector::{
+    self,
+    hierarchy::{Hierarchy, SelectedEntities},
+};
+use bevy_inspector_egui::egui;
+
+use crate::model_stats::triangle_count;
+use crate::scene_setup::SceneHandle;
+
+#[derive(Resource, Default)]
+pub struct HierarchyPanel {
+    pub open: bool,
+    selected: SelectedEntities,
+}
+
+pub fn toggle_hierarchy_panel(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut hierarchy_panel: ResMut<HierarchyPanel>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::H) {
+        hierarchy_panel.open = !hierarchy_panel.open;
+    }
+}
+
+/// Needs the whole world for the tree and the inspector widgets, so it runs exclusively.
+pub fn show_hierarchy_panel(world: &mut World) {
+    if !world.resource::<HierarchyPanel>().open {
+        return;
+    }
+    let Ok(egui_context) = world
+        .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
+        .get_single(world)
+    else {
+        return;
+    };
+    let mut egui_context = egui_context.clone();
+
+    world.resource_scope(|world, mut hierarchy_panel: Mut<HierarchyPanel>| {
+        // forget entities that went away with the models
+        hierarchy_panel
+            .selected
+            .retain(|entity| world.get_entity(entity).is_some());
+
+        egui::Window::new("Hierarchy")
+            .default_pos([10.0, 200.0])
+            .default_width(300.0)
+            .show(egui_context.get_mut(), |ui| {
+                egui::ScrollArea::vertical()
+                    .id_source("hierarchy_tree")
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        let type_registry = world.resource::<AppTypeRegistry>().clone();
+                        let type_registry = type_registry.read();
+                        // only the model roots and what was spawned under them
+                        Hierarchy {
+                            world,
+                            type_registry: &type_registry,
+                            selected: &mut hierarchy_panel.selected,
+                            context_menu: None,
+                            shortcircuit_entity: None,
+                            extra_state: &mut (),
+                        }
+                        .show::<With<SceneHandle>>(ui);
+                    });
+
+                ui.separator();
+                match hierarchy_panel.selected.iter().next() {
+                    Some(entity) => entity_details(world, entity, ui),
+                    None => {
+                        ui.label("Select an entity");
+                    }
+                }
+            });
+    });
+}
+
+fn entity_details(world: &mut World, entity: Entity, ui: &mut egui::Ui) {
+    let name = world
+        .get::<Name>(entity)
+        .map_or_else(|| format!("{:?}", entity), |name| name.to_string());
+    ui.heading(name);
+
+    if let Some(mut visibility) = world.get_mut::<Visibility>(entity) {
+        let mut visible = *visibility != Visibility::Hidden;
+        if ui.checkbox(&mut visible, "Visible").changed() {
+            *visibility = if visible {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+
+    // edit a copy, the inspector widgets need the world too
+    if let Some(transform) = world.get::<Transform>(entity).copied() {
+        let mut edited = transform;
+        ui.collapsing("Transform", |ui| {
+            bevy_inspector::ui_for_value(&mut edited, ui, world);
+        });
+        if edited != transform {
+            if let Some(mut transform) = world.get_mut::<Transform>(entity) {
+                *transform = edited;
+            }
+        }
+    }
+
+    egui::Grid::new("entity_components")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            for (label, value) in component_summary(world, entity) {
+                ui.label(label);
+                ui.label(value);
+                ui.end_row();
+            }
+        });
+}
+
+/// One line for each of the components worth knowing about when checking a model.
+fn component_summary(world: &World, entity: Entity) -> Vec<(&'static str, String)> {
+    let asset_server = world.resource::<AssetServer>();
+    // file name and label, 'alien.glb#Mesh0/Primitive0'
+    let asset_path = |handle: HandleId| match asset_server.get_handle_path(handle) {
+        Some(path) => {
+            let file_name = path
+                .path()
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            match path.label() {
+                Some(label) => format!("{}#{}", file_name, label),
+                None => file_name.to_string(),
+            }
+        }
+        None => "-".to_string(),
+    };
+    let mut summary = Vec::new();
+
+    if let Some(handle) = world.get::<Handle<Mesh>>(entity) {
+        summary.push(("Mesh", asset_path(handle.id())));
+        if let Some(mesh) = world.resource::<Assets<Mesh>>().get(handle) {
+            summary.push((
+                "",
+                format!(
+                    "{} vertices, {} triangles",
+                    mesh.count_vertices(),
+                    triangle_count(mesh)
+                ),
+            ));
+        }
+    }
+    if let Some(handle) = world.get::<Handle<StandardMaterial>>(entity) {
+        summary.push(("Material", asset_path(handle.id())));
+    }
+    if let Some(aabb) = world.get::<Aabb>(entity) {
+        summary.push((
+            "Aabb",
+            format!(
+                "center {:.3} half {:.3}",
+                Vec3::from(aabb.center),
+                Vec3::from(aabb.half_extents)
+            ),
+        ));
+    }
+    if let Some(skinned_mesh) = world.get::<SkinnedMesh>(entity) {
+        summary.push(("Skin", format!("{} joints", skinned_mesh.joints.len())));
+    }
+    if let Some(light) = world.get::<PointLight>(entity) {
+        summary.push((
+            "Point light",
+            format!("{:.0} lm, range {:.2}", light.intensity, light.range),
+        ));
+    }
+    if let Some(light) = world.get::<SpotLight>(entity) {
+        summary.push((
+            "Spot light",
+            format!(
+                "{:.0} lm, range {:.2}, angle {:.1}°",
+                light.intensity,
+                light.range,
+                light.outer_angle.to_degrees()
+            ),
+        ));
+    }
+    if let Some(light) = world.get::<DirectionalLight>(entity) {
+        summary.push(("Directional light", format!("{:.0} lux", light.illuminance)));
+    }
+    if world.get::<Camera>(entity).is_some() {
+        summary.push(("Camera", String::new()));
+    }
+    summary
+}
@@ -1,161 +1,62 @@
-use std::f32::consts::PI;
 use bevy::{
     prelude::*,
-    pbr::{MaterialPipeline, MaterialPipelineKey},
     reflect::TypeUuid,
     render::{
-        mesh::{MeshVertexBufferLayout, PrimitiveTopology},
-        render_resource::{
-            AsBindGroup, PolygonMode, RenderPipelineDescriptor, ShaderRef,
-            SpecializedMeshPipelineError,
-        },
+        mesh::PrimitiveTopology,
+        primitives::Aabb,
+        render_resource::{AsBindGroup, ShaderRef},
     },
 };
-use crate::cylinder::Cylinder;
-
-pub fn setup_lines(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<LineMaterial>>,
-) {
-    // Spawn a list of lines with start and end points for each lines
-    commands.spawn(MaterialMeshBundle {
-        mesh: meshes.add(Mesh::from(LineList {
-            lines: vec![
-                (Vec3::ZERO, Vec3::new(1.0, 0.0, 0.0)),
-                (Vec3::new(1.0, 0.02, 0.0), Vec3::new(1.0, -0.02, 0.0)),
-            ],
-        })),
-        material: materials.add(LineMaterial { color: Color::RED, }),
-        ..default()
-    });
-
-    commands.spawn(MaterialMeshBundle {
-        mesh: meshes.add(Mesh::from(LineList {
-            lines: vec![
-                (Vec3::ZERO, Vec3::new(0.0, 1.0, 0.0)),
-            ],
-        })),
-        material: materials.add(LineMaterial { color: Color::GREEN, }),
-        ..default()
-    });
-
-    commands.spawn(MaterialMeshBundle {
-        mesh: meshes.add(Mesh::from(LineList {
-            lines: vec![
-                (Vec3::ZERO, Vec3::new(0.0, 0.0, 1.0)),
-            ],
-        })),
-        material: materials.add(LineMaterial { color: Color::BLUE, }),
-        ..default()
-    });
-
-
-
-    // // Spawn a line strip that goes from point to point
-    // commands.spawn(MaterialMeshBundle {
-    //     mesh: meshes.add(Mesh::from(LineStrip {
-    //         points: vec![
-    //             Vec3::ZERO,
-    //             Vec3::new(1.0, 1.0, 0.0),
-    //             Vec3::new(1.0, 0.0, 0.0),
-    //         ],
-    //     })),
-    //     transform: Transform::from_xyz(0.5, 0.0, 0.0),
-    //     material: materials.add(LineMaterial { color: Color::BLUE }),
-    //     ..default()
-    // });
-
-    // camera
-    // commands.spawn(Camera3dBundle {
-    //     transform: Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
-    //     ..default()
-    // });
-}
-
-const X_EXTENT: f32 = 14.;
-
-pub fn setup_cylinders(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
-    commands.spawn(MaterialMeshBundle {
-        mesh: meshes.add(Mesh::from(
-            Cylinder {
-                radius: 0.04,
-                height: 2.0,
-                resolution: 20,
-                segments: 10,
-            }
-        )),
-        material: materials.add(Color::rgb(0.63, 0.96, 0.26).into()), // greenish - y up
-        transform: Transform::from_xyz( 0.0, 1.0, 0.0, ),
-            // .with_rotation(Quat::from_rotation_x(-PI / 4.)),
-        ..default()
-    });
-
-    commands.spawn(MaterialMeshBundle {
-        mesh: meshes.add(Mesh::from(
-            Cylinder {
-                radius: 0.04,
-                height: 2.0,
-                resolution: 20,
-                segments: 10,
-            }
-        )),
-        // material: materials.add(LineMaterial { color: Color::YELLOW, }),
-        material: materials.add(Color::rgb(0.96, 0.20, 0.20).into()), // redish - x right
-        transform: Transform::from_xyz( 1.0, 0.0, 0.0, )
-            .with_rotation(Quat::from_rotation_z(PI / 2.)),
-        ..default()
-    });
-
-    commands.spawn(MaterialMeshBundle {
-        mesh: meshes.add(Mesh::from(
-            Cylinder {
-                radius: 0.04,
-                height: 2.0,
-                resolution: 20,
-                segments: 10,
-            }
-        )),
-        // material: materials.add(LineMaterial { color: Color::YELLOW, }),
-        material: materials.add(Color::rgb(0.20, 0.20, 0.96).into()), // bluish z - out
-        transform: Transform::from_xyz( 0.0, 0.0, 1.0, )
-            .with_rotation(Quat::from_rotation_x(PI / 2.)),
-        ..default()
-    });
-}
 
+/// Draws a mesh as lines. Meshes with vertex colors are multiplied by `color`, so use white to
+/// see the vertex colors as they are.
 #[derive(Default, AsBindGroup, TypeUuid, Debug, Clone)]
 #[uuid = "050ce6ac-080a-4d8c-b6b5-b5bab7560d8f"]
 pub struct LineMaterial {
     #[uniform(0)]
-    color: Color,
+    pub color: Color,
 }
 
+// The mesh topology is enough to draw lines, no PolygonMode::Line needed, which not every
+// backend supports
 impl Material for LineMaterial {
     fn fragment_shader() -> ShaderRef {
         "assets/shaders/line_material.wgsl".into()
     }
-
-    fn specialize(
-        _pipeline: &MaterialPipeline<Self>,
-        descriptor: &mut RenderPipelineDescriptor,
-        _layout: &MeshVertexBufferLayout,
-        _key: MaterialPipelineKey<Self>,
-    ) -> Result<(), SpecializedMeshPipelineError> {
-        // This is the important part to tell bevy to render this material as a line between vertices
-        descriptor.primitive.polygon_mode = PolygonMode::Line;
-        Ok(())
-    }
 }
 
 /// A list of lines with a start and end position
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct LineList {
     pub lines: Vec<(Vec3, Vec3)>,
+    /// Start and end color of each line, or empty to use the material color only.
+    pub colors: Vec<(Color, Color)>,
+}
+
+impl LineList {
+    /// The twelve edges of each box.
+    pub fn boxes<'a>(aabbs: impl IntoIterator<Item = &'a Aabb>) -> Self {
+        let mut lines = Vec::new();
+        for aabb in aabbs {
+            let (min, max) = (Vec3::from(aabb.min()), Vec3::from(aabb.max()));
+            let corner = |x: bool, y: bool, z: bool| {
+                Vec3::new(
+                    if x { max.x } else { min.x },
+                    if y { max.y } else { min.y },
+                    if z { max.z } else { min.z },
+                )
+            };
+            for a in [false, true] {
+                for b in [false, true] {
+                    // edges along x, y and z
+                    lines.push((corner(false, a, b), corner(true, a, b)));
+                    lines.push((corner(a, false, b), corner(a, true, b)));
+                    lines.push((corner(a, b, false), corner(a, b, true)));
+                }
+            }
+        }
+        LineList { lines, ..default() }
+    }
 }
 
 impl From<LineList> for Mesh {
@@ -165,24 +66,190 @@ impl From<LineList> for Mesh {
         let mut mesh = Mesh::new(PrimitiveTopology::LineList);
 
         let vertices: Vec<_> = line.lines.into_iter().flat_map(|(a, b)| [a, b]).collect();
+        let colors: Vec<_> = line.colors.into_iter().flat_map(|(a, b)| [a, b]).collect();
+        insert_colors(&mut mesh, &colors, vertices.len());
         mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
         mesh
     }
 }
 
-// /// A list of points that will have a line drawn between each consecutive points
-// #[derive(Debug, Clone)]
-// pub struct LineStrip {
-//     pub points: Vec<Vec3>,
-// }
-//
-// impl From<LineStrip> for Mesh {
-//     fn from(line: LineStrip) -> Self {
-//         // This tells wgpu that the positions are a list of points
-//         // where a line will be drawn between each consecutive point
-//         let mut mesh = Mesh::new(PrimitiveTopology::LineStrip);
-//
-//         mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, line.points);
-//         mesh
-//     }
-// }
+/// A list of points that will have a line drawn between each consecutive points
+#[derive(Debug, Clone, Default)]
+pub struct LineStrip {
+    pub points: Vec<Vec3>,
+    /// Color of each point, blended along the lines, or empty to use the material color only.
+    pub colors: Vec<Color>,
+}
+
+impl From<LineStrip> for Mesh {
+    fn from(line: LineStrip) -> Self {
+        // This tells wgpu that the positions are a list of points
+        // where a line will be drawn between each consecutive point
+        let mut mesh = Mesh::new(PrimitiveTopology::LineStrip);
+
+        insert_colors(&mut mesh, &line.colors, line.points.len());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, line.points);
+        mesh
+    }
+}
+
+/// A line strip that also joins the last point back to the first, e.g. a circle.
+#[derive(Debug, Clone, Default)]
+pub struct LineLoop {
+    pub points: Vec<Vec3>,
+    /// Color of each point, or empty to use the material color only.
+    pub colors: Vec<Color>,
+}
+
+impl From<LineLoop> for LineStrip {
+    fn from(line: LineLoop) -> Self {
+        let mut points = line.points;
+        let mut colors = line.colors;
+        // there is no loop topology, repeat the first point instead
+        if let Some(first) = points.first().copied() {
+            points.push(first);
+        }
+        if let Some(first) = colors.first().copied() {
+            colors.push(first);
+        }
+        LineStrip { points, colors }
+    }
+}
+
+impl From<LineLoop> for Mesh {
+    fn from(line: LineLoop) -> Self {
+        Mesh::from(LineStrip::from(line))
+    }
+}
+
+/// Each pair of consecutive points becomes a line, e.g. to draw a strip with `ThickLines`.
+impl From<LineStrip> for LineList {
+    fn from(line: LineStrip) -> Self {
+        LineList {
+            lines: line.points.windows(2).map(|pair| (pair[0], pair[1])).collect(),
+            colors: line.colors.windows(2).map(|pair| (pair[0], pair[1])).collect(),
+        }
+    }
+}
+
+impl From<LineLoop> for LineList {
+    fn from(line: LineLoop) -> Self {
+        LineList::from(LineStrip::from(line))
+    }
+}
+
+/// Adds the vertex colors to a line mesh, converted to the linear colors the shader works in. A
+/// mismatched count is left out with a warning, drawing the lines in the material color.
+fn insert_colors(mesh: &mut Mesh, colors: &[Color], vertex_count: usize) {
+    if colors.is_empty() {
+        return;
+    }
+    if colors.len() != vertex_count {
+        warn!(
+            "A line mesh needs one color per vertex or none at all, got {} colors for {} vertices",
+            colors.len(),
+            vertex_count
+        );
+        return;
+    }
+    let colors: Vec<[f32; 4]> = colors.iter().map(|color| color.as_linear_rgba_f32()).collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::mesh::VertexAttributeValues;
+
+    use super::*;
+
+    fn positions(mesh: &Mesh) -> &Vec<[f32; 3]> {
+        match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => positions,
+            _ => panic!("no positions"),
+        }
+    }
+
+    fn mesh_colors(mesh: &Mesh) -> Option<&Vec<[f32; 4]>> {
+        match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            Some(VertexAttributeValues::Float32x4(colors)) => Some(colors),
+            None => None,
+            _ => panic!("colors are not RGBA floats"),
+        }
+    }
+
+    fn linear(colors: &[Color]) -> Vec<[f32; 4]> {
+        colors.iter().map(|color| color.as_linear_rgba_f32()).collect()
+    }
+
+    fn triangle() -> Vec<Vec3> {
+        vec![Vec3::ZERO, Vec3::X, Vec3::Y]
+    }
+
+    #[test]
+    fn line_list() {
+        let colors = [Color::RED, Color::GREEN, Color::BLUE, Color::WHITE];
+        let mesh = Mesh::from(LineList {
+            lines: vec![(Vec3::ZERO, Vec3::X), (Vec3::Y, Vec3::Z)],
+            colors: vec![(colors[0], colors[1]), (colors[2], colors[3])],
+        });
+        assert_eq!(mesh.primitive_topology(), PrimitiveTopology::LineList);
+        assert_eq!(positions(&mesh).len(), 4);
+        assert_eq!(mesh_colors(&mesh), Some(&linear(&colors)));
+    }
+
+    #[test]
+    fn line_strip() {
+        let colors = [Color::RED, Color::GREEN, Color::BLUE];
+        let mesh = Mesh::from(LineStrip {
+            points: triangle(),
+            colors: colors.to_vec(),
+        });
+        assert_eq!(mesh.primitive_topology(), PrimitiveTopology::LineStrip);
+        assert_eq!(positions(&mesh).len(), 3);
+        assert_eq!(mesh_colors(&mesh), Some(&linear(&colors)));
+    }
+
+    #[test]
+    fn line_loop_closes() {
+        let colors = [Color::RED, Color::GREEN, Color::BLUE];
+        let mesh = Mesh::from(LineLoop {
+            points: triangle(),
+            colors: colors.to_vec(),
+        });
+        assert_eq!(mesh.primitive_topology(), PrimitiveTopology::LineStrip);
+        // back to the first point, in the first color
+        assert_eq!(positions(&mesh).len(), 4);
+        assert_eq!(positions(&mesh)[3], [0.0, 0.0, 0.0]);
+        assert_eq!(
+            mesh_colors(&mesh),
+            Some(&linear(&[Color::RED, Color::GREEN, Color::BLUE, Color::RED]))
+        );
+
+        let line_list = LineList::from(LineLoop {
+            points: triangle(),
+            colors: Vec::new(),
+        });
+        assert_eq!(line_list.lines.len(), 3);
+        assert_eq!(line_list.lines[2], (Vec3::Y, Vec3::ZERO));
+        assert!(line_list.colors.is_empty());
+    }
+
+    #[test]
+    fn without_colors() {
+        let mesh = Mesh::from(LineStrip {
+            points: triangle(),
+            colors: Vec::new(),
+        });
+        assert_eq!(mesh_colors(&mesh), None);
+    }
+
+    #[test]
+    fn mismatched_colors_are_left_out() {
+        let mesh = Mesh::from(LineStrip {
+            points: triangle(),
+            colors: vec![Color::RED],
+        });
+        assert_eq!(positions(&mesh).len(), 3);
+        assert_eq!(mesh_colors(&mesh), None);
+    }
+}
@@ -9,61 +9,216 @@
 // use bevy::log::LogPlugin;
 use bevy::{
     prelude::*,
+    render::view::VisibilitySystems,
+    transform::TransformSystem,
 };
+use bevy_inspector_egui::bevy_egui::EguiPlugin;
+use bevy_inspector_egui::DefaultInspectorConfigPlugin;
 
+use crate::args::{Command, parse_args, print_help};
+use crate::asset_browser::{AssetBrowser, show_asset_browser, toggle_asset_browser};
+use crate::axis_gizmo::{scale_axis_gizmo, setup_axis_gizmos, show_axis_labels, update_orientation_gizmo};
+use crate::bookmarks::{CameraBookmarks, camera_bookmark_keys, load_camera_bookmarks, recall_camera_bookmark, show_camera_bookmarks};
+use crate::bounds::SceneBounds;
+use crate::bounds_overlay::{BoundsOverlay, show_bounds_labels, toggle_bounds_overlay, update_bounds_overlay};
 use crate::camera::*;
-use crate::camera_pan_orbit::pan_orbit_camera;
+use crate::ground_grid::{GridMaterial, GroundGrid, fade_ground_grid, toggle_ground_grid, update_ground_grid};
+use crate::camera_pan_orbit::{frame_scene, pan_orbit_camera};
+use crate::hierarchy_panel::{HierarchyPanel, show_hierarchy_panel, toggle_hierarchy_panel};
 use crate::lights::update_lights;
-use crate::lines::{LineMaterial, setup_cylinders, setup_lines};
+use crate::lines::LineMaterial;
+use crate::material_editor::{MaterialEditor, forget_material_originals, pick_material_mesh, show_material_editor, toggle_material_editor};
+use crate::mesh_debug::{MeshDebug, follow_mesh_debug, toggle_mesh_debug, update_mesh_debug};
+use crate::model_grid::{ModelGrid, arrange_models, show_model_panel};
+use crate::model_stats::{StatsPanel, collect_model_stats, show_stats_panel, toggle_stats_panel};
 use crate::scene_setup::*;
+use crate::screenshot::{ScreenshotPlugin, save_screenshots, screenshot_key, screenshot_on_start, spawn_screenshot_cameras};
+use crate::thick_lines::ThickLineMaterial;
+use crate::thumbnails::{ThumbnailBatch, run_thumbnail_batch};
+use crate::turntable::{Turntable, TurntableExport, run_turntable_export, turn_turntable};
 
+mod args;
+mod asset_browser;
+mod axis_gizmo;
+mod bookmarks;
+mod bounds;
+mod bounds_overlay;
 mod scene_setup;
 mod camera;
 mod lines;
 mod cylinder;
+mod gif;
+mod ground_grid;
+mod hierarchy_panel;
 mod lights;
 mod camera_pan_orbit;
+mod material_editor;
+mod mesh_debug;
+mod model_grid;
+mod model_stats;
+mod report;
+mod screenshot;
+mod shapes;
+mod thick_lines;
+mod thumbnails;
+mod turntable;
+mod validate;
+mod world_label;
 
 fn main() {
 
-    print_help();
+    let viewer_args = match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Run(viewer_args)) => viewer_args,
+        Ok(Command::Validate(viewer_args)) => {
+            let passed = validate::run_validation(viewer_args);
+            std::process::exit(if passed { 0 } else { 1 });
+        }
+        Ok(Command::Help) => {
+            print_help();
+            return;
+        }
+        Err(error) => {
+            eprintln!("error: {}\n\nFor more information, try '--help'.", error);
+            std::process::exit(2);
+        }
+    };
 
     let ambient_light = AmbientLight {
             color: Color::WHITE,
-            brightness: 1.0 / 5.0f32,
+            brightness: viewer_args.lighting.ambient_brightness(),
         };
 
     let mut app = App::new();
 
     app.insert_resource(ambient_light)
+        .insert_resource(viewer_args.clone())
         .init_resource::<CameraTracker>()
+        .init_resource::<ModelGrid>()
+        .init_resource::<SceneSetup>()
+        .init_resource::<SceneBounds>()
+        .init_resource::<AssetBrowser>()
+        .init_resource::<BoundsOverlay>()
+        .init_resource::<MeshDebug>()
+        .init_resource::<HierarchyPanel>()
+        .init_resource::<MaterialEditor>()
+        .init_resource::<StatsPanel>()
+        .init_resource::<Turntable>()
+        .init_resource::<CameraBookmarks>()
+        .insert_resource(GroundGrid::new(viewer_args.grid, viewer_args.grid_spacing))
+        .add_event::<LoadModels>()
         .add_plugins(
             DefaultPlugins
                 .set(WindowPlugin {
                     primary_window: Some(Window {
                         title: "bevy scene viewer".to_string(),
+                        resolution: (viewer_args.window_width, viewer_args.window_height).into(),
                         ..default()
                     }),
                     ..default()
                 })
                 .set(AssetPlugin {
-                    asset_folder: std::env::var("CARGO_MANIFEST_DIR")
-                        .unwrap_or_else(|_| ".".to_string()),
+                    asset_folder: asset_folder(),
                     watch_for_changes: true,
                 })
                // .build().disable::<LogPlugin>()
         )
+        .add_plugin(EguiPlugin)
+        .add_plugin(DefaultInspectorConfigPlugin)
         .add_plugin(MaterialPlugin::<LineMaterial>::default())
+        .add_plugin(MaterialPlugin::<GridMaterial>::default())
+        .add_plugin(MaterialPlugin::<ThickLineMaterial>::default())
+        .add_plugin(ScreenshotPlugin)
         .add_startup_system(setup_scene)
-        .add_startup_system(setup_lines)
-        .add_startup_system(setup_cylinders)
+        .add_startup_system(setup_axis_gizmos)
+        .add_system(load_models)
         .add_system(scene_load_check)
+        .add_system(switch_scene)
+        .add_system(step_through_directory)
+        .add_system(arrange_models)
+        .add_system(show_load_status)
+        .add_system(show_model_panel)
+        .add_system(collect_model_stats)
+        .add_system(toggle_stats_panel)
+        .add_system(show_stats_panel)
+        .add_system(toggle_bounds_overlay)
+        .add_system(update_bounds_overlay)
+        .add_system(show_bounds_labels)
+        .add_system(scale_axis_gizmo)
+        .add_system(update_orientation_gizmo)
+        .add_system(show_axis_labels)
+        .add_system(toggle_mesh_debug)
+        .add_system(update_mesh_debug)
+        .add_system(
+            follow_mesh_debug
+                .in_base_set(CoreSet::PostUpdate)
+                .after(TransformSystem::TransformPropagate)
+                .before(VisibilitySystems::CheckVisibility)
+        )
+        .add_system(toggle_ground_grid)
+        .add_system(update_ground_grid)
+        .add_system(fade_ground_grid)
+        .add_system(toggle_hierarchy_panel)
+        .add_system(show_hierarchy_panel)
+        .add_system(toggle_material_editor)
+        .add_system(forget_material_originals)
+        .add_system(pick_material_mesh)
+        .add_system(show_material_editor)
+        .add_system(toggle_asset_browser)
+        .add_system(show_asset_browser)
         .add_system(setup_scene_after_load)
+        .add_system(screenshot_key)
+        .add_system(screenshot_on_start)
+        .add_system(spawn_screenshot_cameras)
+        .add_system(save_screenshots)
         .add_system(update_lights)
         .add_system(pan_orbit_camera)
+        .add_system(turn_turntable.after(pan_orbit_camera))
+        .add_system(load_camera_bookmarks)
+        .add_system(camera_bookmark_keys)
+        .add_system(show_camera_bookmarks)
+        .add_system(recall_camera_bookmark.after(pan_orbit_camera))
+        .add_system(frame_scene)
+        .add_system(camera_controller)
         // .add_system(camera_tracker)
     ;
 
+    if let Some(folder) = &viewer_args.thumbnails {
+        match ThumbnailBatch::new(
+            &viewer_args.models,
+            folder,
+            viewer_args.thumbnail_size,
+            viewer_args.transparent,
+        ) {
+            Ok(thumbnail_batch) => {
+                app.insert_resource(thumbnail_batch)
+                    .add_system(run_thumbnail_batch);
+            }
+            Err(error) => {
+                eprintln!("error: could not create '{}': {}", folder, error);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if let Some(folder) = &viewer_args.turntable {
+        match TurntableExport::new(
+            folder,
+            viewer_args.turntable_frames,
+            viewer_args.screenshot_size,
+            viewer_args.transparent,
+            viewer_args.turntable_gif,
+        ) {
+            Ok(turntable_export) => {
+                app.insert_resource(turntable_export)
+                    .add_system(run_turntable_export.after(pan_orbit_camera));
+            }
+            Err(error) => {
+                eprintln!("error: could not create '{}': {}", folder, error);
+                std::process::exit(2);
+            }
+        }
+    }
+
     #[cfg(feature = "animation")]
     app.add_system(start_animation)
         .add_system(keyboard_animation_control);
@@ -72,26 +227,3 @@ fn main() {
 
     app.run();
 }
-
-fn print_help() {
-    println!(
-        "
-Controls:
-    MOUSE       - Move camera orientation
-    LClick/M    - Enable mouse movement
-    WSAD        - forward/back/strafe left/right
-    LShift      - 'run'
-    E           - up
-    Q           - down
-    L           - animate light direction
-    U           - toggle shadows
-    C           - cycle through the camera controller and any cameras loaded from the scene
-    5/6         - decrease/increase shadow projection width
-    7/8         - decrease/increase shadow projection height
-    9/0         - decrease/increase shadow projection near/far
-
-    Space       - Play/Pause animation
-    Enter       - Cycle through animations
-"
-    );
-}
\ No newline at end of file
@@ -0,0 +1,470 @@
+//! A window for tweaking the `StandardMaterial` of a mesh in the loaded models while looking at
+//! the result. Meshes are picked with Ctrl + left click or from a list. The file's values are kept
+//! so a material can be reset, and the edited values can be dumped to copy back into the source.
+
+use std::collections::HashMap;
+
+use bevy::{
+    math::Vec3A,
+    prelude::*,
+    render::{
+        mesh::{Indices, VertexAttributeValues},
+        primitives::Aabb,
+        render_resource::Face,
+    },
+    scene::InstanceId,
+    window::PrimaryWindow,
+};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::scene_setup::SceneHandle;
+use crate::world_label::{active_camera, world_label};
+
+#[derive(Resource, Default)]
+pub struct MaterialEditor {
+    pub open: bool,
+    selected: Option<Entity>,
+    /// Materials as they were loaded, kept from the first edit on.
+    originals: HashMap<Handle<StandardMaterial>, StandardMaterial>,
+    /// The model instances the originals came from.
+    instances: Vec<InstanceId>,
+}
+
+type MaterialMesh<'a> = (
+    Entity,
+    &'a Handle<Mesh>,
+    &'a Handle<StandardMaterial>,
+    &'a GlobalTransform,
+    Option<&'a Name>,
+);
+
+pub fn toggle_material_editor(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut material_editor: ResMut<MaterialEditor>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::K) {
+        material_editor.open = !material_editor.open;
+    }
+}
+
+/// Every mesh with a material in the spawned model instances, in file order. The gizmos and debug
+/// lines aren't part of any instance, so they are left out.
+fn instance_meshes<'a>(
+    scene_handles: &Query<&SceneHandle>,
+    scene_spawner: &SceneSpawner,
+    mesh_entities: &'a Query<MaterialMesh>,
+) -> Vec<MaterialMesh<'a>> {
+    scene_handles
+        .iter()
+        .filter_map(|scene_handle| scene_handle.instance_id())
+        .flat_map(|instance_id| scene_spawner.iter_instance_entities(instance_id))
+        .filter_map(|entity| mesh_entities.get(entity).ok())
+        .collect()
+}
+
+/// Forgets the materials as they were loaded once the models are reloaded, replaced or switched
+/// to another scene, which brings materials of their own.
+pub fn forget_material_originals(
+    scene_handles: Query<&SceneHandle>,
+    mut material_editor: ResMut<MaterialEditor>,
+) {
+    let instances: Vec<_> = scene_handles
+        .iter()
+        .filter_map(|scene_handle| scene_handle.instance_id())
+        .collect();
+    if instances != material_editor.instances {
+        material_editor.originals.clear();
+        material_editor.instances = instances;
+    }
+}
+
+/// Selects the mesh of the loaded models under the cursor on Ctrl + left click.
+#[allow(clippy::too_many_arguments)]
+pub fn pick_material_mesh(
+    mut contexts: EguiContexts,
+    mouse_input: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    scene_spawner: Res<SceneSpawner>,
+    scene_handles: Query<&SceneHandle>,
+    mesh_entities: Query<MaterialMesh>,
+    meshes: Res<Assets<Mesh>>,
+    mut material_editor: ResMut<MaterialEditor>,
+) {
+    if !material_editor.open
+        || !mouse_input.just_pressed(MouseButton::Left)
+        || !keyboard_input.any_pressed([KeyCode::LControl, KeyCode::RControl])
+        || contexts.ctx_mut().is_pointer_over_area()
+    {
+        return;
+    }
+    let Some(cursor_position) = primary_window
+        .get_single()
+        .ok()
+        .and_then(Window::cursor_position)
+    else {
+        return;
+    };
+    let Some(ray) = active_camera(&cameras)
+        .and_then(|(camera, transform)| camera.viewport_to_world(transform, cursor_position))
+    else {
+        return;
+    };
+
+    let mut closest = None;
+    for (entity, mesh_handle, _, transform, _) in
+        instance_meshes(&scene_handles, &scene_spawner, &mesh_entities)
+    {
+        let Some(mesh) = meshes.get(mesh_handle) else {
+            continue;
+        };
+        // in the mesh's own space the ray parameter still measures world distance
+        let to_local = transform.compute_matrix().inverse();
+        let origin = to_local.transform_point3(ray.origin);
+        let direction = to_local.transform_vector3(ray.direction);
+
+        if let Some(distance) = ray_mesh_distance(origin, direction, mesh) {
+            if closest.is_none_or(|(_, closest_distance)| distance < closest_distance) {
+                closest = Some((entity, distance));
+            }
+        }
+    }
+    if let Some((entity, _)) = closest {
+        material_editor.selected = Some(entity);
+    }
+}
+
+/// How far along the ray the nearest triangle of `mesh` is, if the ray hits one. Checks the
+/// mesh's bounds first.
+pub fn ray_mesh_distance(origin: Vec3, direction: Vec3, mesh: &Mesh) -> Option<f32> {
+    let aabb = mesh.compute_aabb()?;
+    ray_aabb_distance(origin, direction, &aabb)?;
+
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(Indices::U16(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        Some(Indices::U32(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        None => (0..positions.len()).collect(),
+    };
+
+    indices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(positions[triangle[i]]));
+            ray_triangle_distance(origin, direction, a, b, c)
+        })
+        .min_by(|a, b| a.total_cmp(b))
+}
+
+/// Slab test, the distance to where the ray enters the box.
+fn ray_aabb_distance(origin: Vec3, direction: Vec3, aabb: &Aabb) -> Option<f32> {
+    let (min, max) = (Vec3::from(aabb.min()), Vec3::from(aabb.max()));
+    let inverse = direction.recip();
+    let (t1, t2) = ((min - origin) * inverse, (max - origin) * inverse);
+    let near = t1.min(t2).max_element();
+    let far = t1.max(t2).min_element();
+    (far >= near.max(0.0)).then_some(near.max(0.0))
+}
+
+/// Möller–Trumbore, both sides of the triangle count.
+fn ray_triangle_distance(origin: Vec3, direction: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+    let (edge1, edge2) = (b - a, c - a);
+    let p = direction.cross(edge2);
+    let determinant = edge1.dot(p);
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+    let to_origin = origin - a;
+    let u = to_origin.dot(p) / determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = to_origin.cross(edge1);
+    let v = direction.dot(q) / determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(q) / determinant;
+    (t > 0.0).then_some(t)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn show_material_editor(
+    mut contexts: EguiContexts,
+    mut material_editor: ResMut<MaterialEditor>,
+    scene_spawner: Res<SceneSpawner>,
+    scene_handles: Query<&SceneHandle>,
+    mesh_entities: Query<MaterialMesh>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    meshes: Res<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !material_editor.open {
+        return;
+    }
+    let material_editor = &mut *material_editor;
+    if material_editor
+        .selected
+        .is_some_and(|entity| !mesh_entities.contains(entity))
+    {
+        material_editor.selected = None;
+    }
+    let ctx = contexts.ctx_mut();
+
+    egui::Window::new("Material")
+        .default_pos([10.0, 520.0])
+        .default_width(280.0)
+        .show(ctx, |ui| {
+            let instance_meshes = instance_meshes(&scene_handles, &scene_spawner, &mesh_entities);
+
+            let selected_text = material_editor
+                .selected
+                .and_then(|entity| mesh_entities.get(entity).ok())
+                .map_or_else(
+                    || "Ctrl + click a mesh".to_string(),
+                    |mesh| mesh_name(&mesh),
+                );
+            egui::ComboBox::from_id_source("material_mesh")
+                .width(240.0)
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for mesh in &instance_meshes {
+                        ui.selectable_value(
+                            &mut material_editor.selected,
+                            Some(mesh.0),
+                            mesh_name(mesh),
+                        );
+                    }
+                });
+
+            let Some((_, _, material_handle, _, _)) = material_editor
+                .selected
+                .and_then(|entity| mesh_entities.get(entity).ok())
+            else {
+                return;
+            };
+            let shared_by = instance_meshes
+                .iter()
+                .filter(|(_, _, handle, _, _)| *handle == material_handle)
+                .count();
+            if shared_by > 1 {
+                ui.label(format!("shared by {} meshes", shared_by));
+            }
+            ui.separator();
+
+            let Some(material) = materials.get(material_handle) else {
+                return;
+            };
+            let mut edited = material.clone();
+            material_ui(ui, &mut edited);
+
+            ui.horizontal(|ui| {
+                let original = material_editor.originals.get(material_handle);
+                if ui
+                    .add_enabled(original.is_some(), egui::Button::new("Reset to file"))
+                    .clicked()
+                {
+                    edited = original.unwrap().clone();
+                }
+                if ui.button("Dump").clicked() {
+                    let dump = dump_material(&edited);
+                    info!("{}", dump);
+                    ui.output_mut(|output| output.copied_text = dump);
+                }
+            });
+
+            if material_changed(material, &edited) {
+                material_editor
+                    .originals
+                    .entry(material_handle.clone())
+                    .or_insert_with(|| material.clone());
+                if let Some(material) = materials.get_mut(material_handle) {
+                    *material = edited;
+                }
+            }
+        });
+
+    // name the picked mesh where it is
+    let (Some(camera), Some((_, mesh_handle, _, transform, name))) = (
+        active_camera(&cameras),
+        material_editor
+            .selected
+            .and_then(|entity| mesh_entities.get(entity).ok()),
+    ) else {
+        return;
+    };
+    if let Some(aabb) = meshes.get(mesh_handle).and_then(Mesh::compute_aabb) {
+        let top = Vec3::from(aabb.center + Vec3A::Y * aabb.half_extents.y);
+        let label = name.map_or_else(|| "selected".to_string(), |name| name.to_string());
+        world_label(
+            ctx,
+            "material_mesh_label",
+            camera,
+            transform.transform_point(top),
+            label,
+        );
+    }
+}
+
+fn mesh_name((entity, _, _, _, name): &MaterialMesh) -> String {
+    name.map_or_else(|| format!("{:?}", entity), |name| name.to_string())
+}
+
+fn material_ui(ui: &mut egui::Ui, material: &mut StandardMaterial) {
+    egui::Grid::new("material_fields")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Base color");
+            color_ui(ui, &mut material.base_color, true);
+            ui.end_row();
+
+            ui.label("Metallic");
+            ui.add(egui::Slider::new(&mut material.metallic, 0.0..=1.0));
+            ui.end_row();
+
+            ui.label("Roughness");
+            ui.add(egui::Slider::new(
+                &mut material.perceptual_roughness,
+                0.0..=1.0,
+            ));
+            ui.end_row();
+
+            ui.label("Reflectance");
+            ui.add(egui::Slider::new(&mut material.reflectance, 0.0..=1.0));
+            ui.end_row();
+
+            ui.label("Emissive");
+            color_ui(ui, &mut material.emissive, false);
+            ui.end_row();
+
+            ui.label("Alpha mode");
+            alpha_mode_ui(ui, &mut material.alpha_mode);
+            ui.end_row();
+
+            ui.label("Double sided");
+            if ui.checkbox(&mut material.double_sided, "").changed() {
+                // the same as the glTF loader does
+                material.cull_mode = if material.double_sided {
+                    None
+                } else {
+                    Some(Face::Back)
+                };
+            }
+            ui.end_row();
+
+            ui.label("Unlit");
+            ui.checkbox(&mut material.unlit, "");
+            ui.end_row();
+        });
+}
+
+/// Edits the color in sRGB, the way it is written in the file.
+fn color_ui(ui: &mut egui::Ui, color: &mut Color, alpha: bool) {
+    let [r, g, b, a] = color.as_rgba_f32();
+    let mut rgba = [r, g, b, a];
+    let changed = if alpha {
+        ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed()
+    } else {
+        let mut rgb = [r, g, b];
+        let changed = ui.color_edit_button_rgb(&mut rgb).changed();
+        rgba = [rgb[0], rgb[1], rgb[2], a];
+        changed
+    };
+    if changed {
+        *color = Color::rgba(rgba[0], rgba[1], rgba[2], rgba[3]);
+    }
+}
+
+fn alpha_mode_ui(ui: &mut egui::Ui, alpha_mode: &mut AlphaMode) {
+    let cutoff = match alpha_mode {
+        AlphaMode::Mask(cutoff) => *cutoff,
+        _ => 0.5,
+    };
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source("alpha_mode")
+            .selected_text(alpha_mode_name(alpha_mode))
+            .show_ui(ui, |ui| {
+                for mode in [
+                    AlphaMode::Opaque,
+                    AlphaMode::Mask(cutoff),
+                    AlphaMode::Blend,
+                    AlphaMode::Premultiplied,
+                    AlphaMode::Add,
+                    AlphaMode::Multiply,
+                ] {
+                    let name = alpha_mode_name(&mode);
+                    ui.selectable_value(alpha_mode, mode, name);
+                }
+            });
+        if let AlphaMode::Mask(cutoff) = alpha_mode {
+            ui.add(
+                egui::DragValue::new(cutoff)
+                    .speed(0.01)
+                    .clamp_range(0.0..=1.0),
+            );
+        }
+    });
+}
+
+fn alpha_mode_name(alpha_mode: &AlphaMode) -> &'static str {
+    match alpha_mode {
+        AlphaMode::Opaque => "Opaque",
+        AlphaMode::Mask(_) => "Mask",
+        AlphaMode::Blend => "Blend",
+        AlphaMode::Premultiplied => "Premultiplied",
+        AlphaMode::Add => "Add",
+        AlphaMode::Multiply => "Multiply",
+    }
+}
+
+/// Only the values the editor changes, so there's no false change every frame.
+fn material_changed(material: &StandardMaterial, edited: &StandardMaterial) -> bool {
+    material.base_color != edited.base_color
+        || material.metallic != edited.metallic
+        || material.perceptual_roughness != edited.perceptual_roughness
+        || material.reflectance != edited.reflectance
+        || material.emissive != edited.emissive
+        || material.alpha_mode != edited.alpha_mode
+        || material.double_sided != edited.double_sided
+        || material.cull_mode != edited.cull_mode
+        || material.unlit != edited.unlit
+}
+
+/// The edited values as Rust, sRGB colors like in the file.
+pub fn dump_material(material: &StandardMaterial) -> String {
+    let rgba = |color: Color| {
+        let [r, g, b, a] = color.as_rgba_f32();
+        format!("Color::rgba({:.3}, {:.3}, {:.3}, {:.3})", r, g, b, a)
+    };
+    format!(
+        "StandardMaterial {{
+    base_color: {},
+    metallic: {:.3},
+    perceptual_roughness: {:.3},
+    reflectance: {:.3},
+    emissive: {},
+    alpha_mode: AlphaMode::{:?},
+    double_sided: {},
+    unlit: {},
+    ..default()
+}}",
+        rgba(material.base_color),
+        material.metallic,
+        material.perceptual_roughness,
+        material.reflectance,
+        rgba(material.emissive),
+        material.alpha_mode,
+        material.double_sided,
+        material.unlit,
+    )
+}
@@ -0,0 +1,257 @@
+//! Debug lines for the meshes of the loaded models: vertex normals, tangents and bitangents, and
+//! a wireframe over the shaded surface, for catching broken normals in exported models.
+//!
+//! The lines are spawned on their own rather than as children of the mesh entities, so they stay
+//! out of the hierarchy panel, and copy their mesh entity's global transform to move with it.
+//! Skinned meshes show the lines of their bind pose.
+
+use std::collections::HashSet;
+
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, MeshVertexAttributeId, PrimitiveTopology, VertexAttributeValues},
+};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+
+use crate::bounds::SceneBounds;
+use crate::lines::{LineList, LineMaterial};
+use crate::scene_setup::SceneHandle;
+
+const NORMAL_COLOR: Color = Color::rgb(0.2, 0.6, 1.0);
+const TANGENT_COLOR: Color = Color::rgb(1.0, 0.3, 0.3);
+const BITANGENT_COLOR: Color = Color::rgb(0.4, 1.0, 0.4);
+const WIREFRAME_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+/// Length of the vertex lines as a fraction of the mesh's largest size.
+const VERTEX_LINE_LENGTH: f32 = 0.05;
+/// How far the wireframe floats above the surface along the normals, as a fraction of the mesh's
+/// largest size, so it isn't hidden by the triangles it outlines.
+const WIREFRAME_OFFSET: f32 = 0.001;
+
+#[derive(Resource, Default)]
+pub struct MeshDebug {
+    pub normals: bool,
+    pub tangents: bool,
+    pub bitangents: bool,
+    pub wireframe: bool,
+}
+
+/// Lines drawn for the mesh on `mesh_entity`.
+#[derive(Component)]
+pub struct MeshDebugLines {
+    mesh_entity: Entity,
+}
+
+pub fn toggle_mesh_debug(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mesh_debug: ResMut<MeshDebug>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::N) {
+        mesh_debug.normals = !mesh_debug.normals;
+    }
+    if keyboard_input.just_pressed(KeyCode::T) {
+        mesh_debug.tangents = !mesh_debug.tangents;
+    }
+    if keyboard_input.just_pressed(KeyCode::Y) {
+        mesh_debug.bitangents = !mesh_debug.bitangents;
+    }
+    if keyboard_input.just_pressed(KeyCode::V) {
+        mesh_debug.wireframe = !mesh_debug.wireframe;
+    }
+}
+
+/// Rebuilds the lines when a toggle changes or new models have loaded.
+#[allow(clippy::too_many_arguments)]
+pub fn update_mesh_debug(
+    mut commands: Commands,
+    mesh_debug: Res<MeshDebug>,
+    scene_bounds: Res<SceneBounds>,
+    scene_spawner: Res<SceneSpawner>,
+    scene_handles: Query<&SceneHandle>,
+    mesh_handles: Query<&Handle<Mesh>>,
+    debug_lines: Query<Entity, With<MeshDebugLines>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LineMaterial>>,
+) {
+    if !mesh_debug.is_changed() && !scene_bounds.is_changed() {
+        return;
+    }
+
+    for entity in &debug_lines {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let mesh_entities = scene_handles
+        .iter()
+        .filter_map(|scene_handle| scene_handle.instance_id())
+        .flat_map(|instance_id| scene_spawner.iter_instance_entities(instance_id))
+        .filter_map(|entity| Some((entity, mesh_handles.get(entity).ok()?)));
+
+    let mut line_lists = Vec::new();
+    for (entity, mesh_handle) in mesh_entities {
+        let Some(mesh) = meshes.get(mesh_handle) else {
+            continue;
+        };
+        let Some(size) = mesh
+            .compute_aabb()
+            .map(|aabb| 2.0 * aabb.half_extents.max_element())
+        else {
+            continue;
+        };
+        let length = VERTEX_LINE_LENGTH * size;
+
+        if mesh_debug.normals {
+            line_lists.push((entity, normal_lines(mesh, length), NORMAL_COLOR));
+        }
+        if mesh_debug.tangents {
+            line_lists.push((entity, tangent_lines(mesh, length, false), TANGENT_COLOR));
+        }
+        if mesh_debug.bitangents {
+            line_lists.push((entity, tangent_lines(mesh, length, true), BITANGENT_COLOR));
+        }
+        if mesh_debug.wireframe {
+            line_lists.push((
+                entity,
+                wireframe_lines(mesh, WIREFRAME_OFFSET * size),
+                WIREFRAME_COLOR,
+            ));
+        }
+    }
+
+    for (entity, line_list, color) in line_lists {
+        // meshes without normals or tangents have nothing to show
+        let Some(line_list) = line_list else {
+            continue;
+        };
+        commands.spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(Mesh::from(line_list)),
+                material: materials.add(LineMaterial { color }),
+                ..default()
+            },
+            MeshDebugLines {
+                mesh_entity: entity,
+            },
+        ));
+    }
+}
+
+/// Moves the lines to where their mesh is and hides them with it, removing them once the mesh is
+/// gone. Runs after the transforms are propagated, so the lines don't lag a frame behind.
+pub fn follow_mesh_debug(
+    mut commands: Commands,
+    mesh_entities: Query<(&GlobalTransform, &ComputedVisibility), Without<MeshDebugLines>>,
+    mut debug_lines: Query<(
+        Entity,
+        &MeshDebugLines,
+        &mut GlobalTransform,
+        &mut Visibility,
+    )>,
+) {
+    for (entity, lines, mut global_transform, mut visibility) in &mut debug_lines {
+        let Ok((mesh_transform, mesh_visibility)) = mesh_entities.get(lines.mesh_entity) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        *global_transform = *mesh_transform;
+        let mesh_visibility = if mesh_visibility.is_visible_in_hierarchy() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != mesh_visibility {
+            *visibility = mesh_visibility;
+        }
+    }
+}
+
+fn float3_attribute(
+    mesh: &Mesh,
+    attribute: impl Into<MeshVertexAttributeId>,
+) -> Option<&Vec<[f32; 3]>> {
+    match mesh.attribute(attribute) {
+        Some(VertexAttributeValues::Float32x3(values)) => Some(values),
+        _ => None,
+    }
+}
+
+/// A line of `length` from every vertex along its normal, in the mesh's own space.
+pub fn normal_lines(mesh: &Mesh, length: f32) -> Option<LineList> {
+    let positions = float3_attribute(mesh, Mesh::ATTRIBUTE_POSITION)?;
+    let normals = float3_attribute(mesh, Mesh::ATTRIBUTE_NORMAL)?;
+
+    let lines = positions
+        .iter()
+        .zip(normals)
+        .map(|(position, normal)| {
+            let position = Vec3::from(*position);
+            (position, position + Vec3::from(*normal) * length)
+        })
+        .collect();
+    Some(LineList { lines, ..default() })
+}
+
+/// A line of `length` from every vertex along its tangent, or along its bitangent, which is
+/// worked out from the normal and the tangent's handedness the same way the shaders do.
+pub fn tangent_lines(mesh: &Mesh, length: f32, bitangent: bool) -> Option<LineList> {
+    let positions = float3_attribute(mesh, Mesh::ATTRIBUTE_POSITION)?;
+    let normals = float3_attribute(mesh, Mesh::ATTRIBUTE_NORMAL)?;
+    let Some(VertexAttributeValues::Float32x4(tangents)) = mesh.attribute(Mesh::ATTRIBUTE_TANGENT)
+    else {
+        return None;
+    };
+
+    let lines = positions
+        .iter()
+        .zip(normals)
+        .zip(tangents)
+        .map(|((position, normal), tangent)| {
+            let position = Vec3::from(*position);
+            let tangent = Vec4::from(*tangent);
+            let direction = if bitangent {
+                Vec3::from(*normal).cross(tangent.truncate()) * tangent.w
+            } else {
+                tangent.truncate()
+            };
+            (position, position + direction.normalize_or_zero() * length)
+        })
+        .collect();
+    Some(LineList { lines, ..default() })
+}
+
+/// Every triangle edge once, moved `offset` out along the vertex normals when there are any.
+pub fn wireframe_lines(mesh: &Mesh, offset: f32) -> Option<LineList> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return None;
+    }
+    let positions = float3_attribute(mesh, Mesh::ATTRIBUTE_POSITION)?;
+    let normals = float3_attribute(mesh, Mesh::ATTRIBUTE_NORMAL);
+    let vertex = |index: usize| {
+        let normal = normals.map_or(Vec3::ZERO, |normals| Vec3::from(normals[index]));
+        Vec3::from(positions[index]) + normal * offset
+    };
+
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(Indices::U16(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        Some(Indices::U32(indices)) => indices.iter().map(|i| *i as usize).collect(),
+        None => (0..positions.len()).collect(),
+    };
+
+    let mut edges = HashSet::new();
+    let mut lines = Vec::new();
+    for triangle in indices.chunks_exact(3) {
+        for (a, b) in [
+            (triangle[0], triangle[1]),
+            (triangle[1], triangle[2]),
+            (triangle[2], triangle[0]),
+        ] {
+            if edges.insert((a.min(b), a.max(b))) {
+                lines.push((vertex(a), vertex(b)));
+            }
+        }
+    }
+    Some(LineList { lines, ..default() })
+}
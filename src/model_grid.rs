@@ -0,0 +1,188 @@
+//! Lays out several models side by side on a grid sized from their bounds, with a panel to show
+//! or hide each model and a label floating above it.
+
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::args::ViewerArgs;
+use crate::bounds::MeshBounds;
+use crate::scene_setup::SceneHandle;
+use crate::world_label::{active_camera, world_label};
+
+/// How long `arrange_models` waits for the meshes of the spawned models to get their bounds, in
+/// seconds, before leaving out the ones that still have none.
+pub const MESH_BOUNDS_TIMEOUT: f32 = 10.0;
+
+#[derive(Resource, Default)]
+pub struct ModelGrid {
+    arranged: bool,
+    /// When `arrange_models` started waiting for mesh bounds, in seconds since startup.
+    waiting_since: Option<f32>,
+}
+
+impl ModelGrid {
+    /// True once every model has loaded or failed and the loaded ones have been placed.
+    pub fn is_arranged(&self) -> bool {
+        self.arranged
+    }
+
+    /// Lay the models out again, e.g. after one of them changed size.
+    pub fn rearrange(&mut self) {
+        self.arranged = false;
+        self.waiting_since = None;
+    }
+}
+
+/// Cell centers on the XZ plane for models with the given footprints (their size along X and Z).
+/// Models are placed row by row on a square-ish grid whose cells fit the largest footprint plus
+/// `spacing`, and the grid is centered on the origin.
+pub fn grid_layout(footprints: &[Vec2], spacing: f32) -> Vec<Vec2> {
+    if footprints.is_empty() {
+        return Vec::new();
+    }
+
+    let columns = (footprints.len() as f32).sqrt().ceil() as usize;
+    let rows = footprints.len().div_ceil(columns);
+    let cell = footprints
+        .iter()
+        .fold(Vec2::ZERO, |cell, footprint| cell.max(*footprint))
+        + Vec2::splat(spacing);
+    let origin = -0.5 * (Vec2::new(columns as f32, rows as f32) - Vec2::ONE) * cell;
+
+    (0..footprints.len())
+        .map(|i| origin + Vec2::new((i % columns) as f32, (i / columns) as f32) * cell)
+        .collect()
+}
+
+/// Once all models are spawned, works out each model's bounds and moves the models onto the grid.
+/// A single model is left where the file put it. Meshes that still have no bounds after
+/// `MESH_BOUNDS_TIMEOUT`, e.g. because their asset never arrived, are left out with a warning.
+pub fn arrange_models(
+    mut model_grid: ResMut<ModelGrid>,
+    time: Res<Time>,
+    viewer_args: Res<ViewerArgs>,
+    scene_spawner: Res<SceneSpawner>,
+    mut models: Query<(&mut SceneHandle, &mut Transform, &GlobalTransform)>,
+    mesh_bounds: MeshBounds,
+) {
+    // the models of a new load are spawned by commands, so they may not be here yet
+    if model_grid.arranged || models.is_empty() {
+        return;
+    }
+    // wait until every model has either spawned or failed
+    if models
+        .iter()
+        .any(|(scene_handle, _, _)| !scene_handle.is_loaded() && !scene_handle.is_failed())
+    {
+        return;
+    }
+
+    let now = time.elapsed_seconds();
+    let timed_out = now - *model_grid.waiting_since.get_or_insert(now) > MESH_BOUNDS_TIMEOUT;
+    let mut pending = false;
+    for (mut scene_handle, _, root) in &mut models {
+        if !scene_handle.is_loaded() || scene_handle.bounds().is_some() {
+            continue;
+        }
+        let Some(instance_id) = scene_handle.instance_id() else {
+            continue;
+        };
+        let to_root = root.compute_matrix().inverse();
+        let bounds = |skip_pending| {
+            mesh_bounds.instance_bounds(
+                &scene_spawner,
+                instance_id,
+                &to_root,
+                viewer_args.bounds_mode,
+                skip_pending,
+            )
+        };
+        match bounds(false) {
+            Some(bounds) => scene_handle.set_bounds(bounds),
+            None if timed_out => {
+                warn!(
+                    "{}: some meshes still have no bounds after {}s, leaving them out",
+                    scene_handle.path(),
+                    MESH_BOUNDS_TIMEOUT
+                );
+                scene_handle.set_bounds(bounds(true).unwrap());
+            }
+            // the mesh bounds are calculated after spawning, try again next frame
+            None => pending = true,
+        }
+    }
+    if pending {
+        return;
+    }
+
+    let footprints: Vec<Vec2> = models
+        .iter()
+        .filter_map(|(scene_handle, _, _)| scene_handle.bounds())
+        .map(|bounds| 2.0 * Vec2::new(bounds.half_extents.x, bounds.half_extents.z))
+        .collect();
+
+    if footprints.len() > 1 {
+        let positions = grid_layout(&footprints, viewer_args.model_spacing);
+        let placed = models
+            .iter_mut()
+            .filter(|(scene_handle, _, _)| scene_handle.bounds().is_some());
+
+        for ((scene_handle, mut transform, _), position) in placed.zip(positions) {
+            let center = scene_handle.bounds().unwrap().center;
+            transform.translation = Vec3::new(position.x - center.x, 0.0, position.y - center.z);
+        }
+    }
+
+    model_grid.arranged = true;
+}
+
+/// A panel with a checkbox per model to show or hide it, and each model's name above it.
+pub fn show_model_panel(
+    mut contexts: EguiContexts,
+    mut models: Query<(Entity, &SceneHandle, &GlobalTransform, &mut Visibility)>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    if models.iter().count() < 2 {
+        return;
+    }
+    let ctx = contexts.ctx_mut();
+
+    egui::Window::new("Models")
+        .anchor(egui::Align2::LEFT_TOP, [10.0, 10.0])
+        .resizable(false)
+        .show(ctx, |ui| {
+            for (_, scene_handle, _, mut visibility) in &mut models {
+                let mut visible = *visibility != Visibility::Hidden;
+                if ui.checkbox(&mut visible, scene_handle.label()).changed() {
+                    *visibility = if visible {
+                        Visibility::Inherited
+                    } else {
+                        Visibility::Hidden
+                    };
+                }
+            }
+        });
+
+    let Some(camera) = active_camera(&cameras) else {
+        return;
+    };
+
+    for (entity, scene_handle, transform, visibility) in &models {
+        let Some(bounds) = scene_handle.bounds() else {
+            continue;
+        };
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+
+        let top = Vec3::from(bounds.center) + Vec3::Y * bounds.half_extents.y;
+        world_label(
+            ctx,
+            ("model_label", entity),
+            camera,
+            transform.transform_point(top),
+            scene_handle.label(),
+        );
+    }
+}
@@ -0,0 +1,394 @@
+//! A summary of each loaded model: what the file holds and what the spawned scene draws. Worked
+//! out once a model has its bounds, logged, and shown in a window.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use bevy::{
+    ecs::system::SystemParam,
+    gltf::{Gltf, GltfMesh},
+    prelude::*,
+    render::{mesh::skinning::SkinnedMesh, render_resource::PrimitiveTopology},
+};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use bevy_inspector_egui::egui;
+
+use crate::scene_setup::SceneHandle;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationStats {
+    pub name: Option<String>,
+    /// Seconds.
+    pub duration: f32,
+}
+
+/// Counts for one model. Nodes, meshes, materials, textures and animations are those of the whole
+/// file; triangles, vertices and skins are those of the spawned scene, counting every instance of a
+/// mesh that is drawn more than once.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct ModelStats {
+    pub path: String,
+    /// The scene the scene counts are for.
+    pub scene_index: usize,
+    pub nodes: usize,
+    pub meshes: usize,
+    pub primitives: usize,
+    pub triangles: usize,
+    pub vertices: usize,
+    pub materials: usize,
+    /// Width and height of each texture the materials use, in the order the materials use them.
+    pub textures: Vec<UVec2>,
+    pub animations: Vec<AnimationStats>,
+    pub skins: usize,
+    pub joints: usize,
+    /// Width, height and depth of the model's bounds.
+    pub size: Vec3,
+}
+
+impl ModelStats {
+    pub fn new(
+        path: &str,
+        scene_index: usize,
+        gltf: &Gltf,
+        scene: &mut Scene,
+        assets: &StatsAssets,
+        size: Vec3,
+    ) -> Self {
+        let StatsAssets {
+            gltf_meshes,
+            meshes,
+            materials,
+            images,
+            animation_clips,
+        } = assets;
+        let primitives = gltf
+            .meshes
+            .iter()
+            .filter_map(|handle| gltf_meshes.get(handle))
+            .map(|gltf_mesh| gltf_mesh.primitives.len())
+            .sum();
+
+        let mut textures = Vec::new();
+        let mut seen = HashSet::new();
+        for material in gltf
+            .materials
+            .iter()
+            .filter_map(|handle| materials.get(handle))
+        {
+            for texture in [
+                &material.base_color_texture,
+                &material.metallic_roughness_texture,
+                &material.normal_map_texture,
+                &material.occlusion_texture,
+                &material.emissive_texture,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                if seen.insert(texture.id()) {
+                    if let Some(image) = images.get(texture) {
+                        textures.push(image.size().as_uvec2());
+                    }
+                }
+            }
+        }
+
+        let animations = gltf
+            .animations
+            .iter()
+            .filter_map(|handle| {
+                let clip = animation_clips.get(handle)?;
+                let name = gltf
+                    .named_animations
+                    .iter()
+                    .find(|(_, named)| *named == handle)
+                    .map(|(name, _)| name.clone());
+                Some(AnimationStats {
+                    name,
+                    duration: clip.duration(),
+                })
+            })
+            .collect();
+
+        let (mut triangles, mut vertices) = (0, 0);
+        for mesh in scene
+            .world
+            .query::<&Handle<Mesh>>()
+            .iter(&scene.world)
+            .filter_map(|handle| meshes.get(handle))
+        {
+            triangles += triangle_count(mesh);
+            vertices += mesh.count_vertices();
+        }
+
+        let mut skins = HashSet::new();
+        let mut joints = HashSet::new();
+        for skinned_mesh in scene.world.query::<&SkinnedMesh>().iter(&scene.world) {
+            skins.insert(skinned_mesh.inverse_bindposes.id());
+            joints.extend(skinned_mesh.joints.iter().copied());
+        }
+
+        ModelStats {
+            path: path.to_string(),
+            scene_index,
+            nodes: gltf.nodes.len(),
+            meshes: gltf.meshes.len(),
+            primitives,
+            triangles,
+            vertices,
+            materials: gltf.materials.len(),
+            textures,
+            animations,
+            skins: skins.len(),
+            joints: joints.len(),
+            size,
+        }
+    }
+}
+
+impl fmt::Display for ModelStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} (scene {})", self.path, self.scene_index)?;
+        writeln!(
+            f,
+            "  {} nodes, {} meshes, {} primitives",
+            self.nodes, self.meshes, self.primitives
+        )?;
+        writeln!(
+            f,
+            "  {} triangles, {} vertices",
+            self.triangles, self.vertices
+        )?;
+        writeln!(
+            f,
+            "  {} materials, {} textures",
+            self.materials,
+            self.textures.len()
+        )?;
+        for texture in &self.textures {
+            writeln!(f, "    {}x{}", texture.x, texture.y)?;
+        }
+        writeln!(f, "  {} animations", self.animations.len())?;
+        for animation in &self.animations {
+            writeln!(
+                f,
+                "    {} {:.2}s",
+                animation.name.as_deref().unwrap_or("-"),
+                animation.duration
+            )?;
+        }
+        writeln!(f, "  {} skins, {} joints", self.skins, self.joints)?;
+        write!(
+            f,
+            "  size {:.3} x {:.3} x {:.3}",
+            self.size.x, self.size.y, self.size.z
+        )
+    }
+}
+
+/// The assets a model's statistics are counted from.
+#[derive(SystemParam)]
+pub struct StatsAssets<'w> {
+    gltf_meshes: Res<'w, Assets<GltfMesh>>,
+    meshes: Res<'w, Assets<Mesh>>,
+    materials: Res<'w, Assets<StandardMaterial>>,
+    images: Res<'w, Assets<Image>>,
+    animation_clips: Res<'w, Assets<AnimationClip>>,
+}
+
+/// Triangles drawn by `mesh`, none for point and line meshes.
+pub fn triangle_count(mesh: &Mesh) -> usize {
+    let count = mesh
+        .indices()
+        .map_or(mesh.count_vertices(), |indices| indices.len());
+    match mesh.primitive_topology() {
+        PrimitiveTopology::TriangleList => count / 3,
+        PrimitiveTopology::TriangleStrip => count.saturating_sub(2),
+        _ => 0,
+    }
+}
+
+/// Whether the statistics window is shown.
+#[derive(Resource)]
+pub struct StatsPanel {
+    pub open: bool,
+}
+
+impl Default for StatsPanel {
+    fn default() -> Self {
+        StatsPanel { open: true }
+    }
+}
+
+/// Works out the statistics of each model once it has its bounds, again after Tab switched its
+/// scene, and logs them.
+pub fn collect_model_stats(
+    mut commands: Commands,
+    scene_handles: Query<(Entity, &SceneHandle, Option<&ModelStats>)>,
+    gltf_assets: Res<Assets<Gltf>>,
+    mut scenes: ResMut<Assets<Scene>>,
+    assets: StatsAssets,
+) {
+    for (entity, scene_handle, maybe_stats) in &scene_handles {
+        let (Some(bounds), Some(scene_index)) = (scene_handle.bounds(), scene_handle.scene_index())
+        else {
+            continue;
+        };
+        if maybe_stats.is_some_and(|stats| stats.scene_index == scene_index) {
+            continue;
+        }
+        let Some(gltf) = gltf_assets.get(scene_handle.gltf()) else {
+            continue;
+        };
+        let Some(scene) = scenes.get_mut(&gltf.scenes[scene_index]) else {
+            continue;
+        };
+
+        let stats = ModelStats::new(
+            scene_handle.path(),
+            scene_index,
+            gltf,
+            scene,
+            &assets,
+            2.0 * Vec3::from(bounds.half_extents),
+        );
+        info!("{}", stats);
+        commands.entity(entity).insert(stats);
+    }
+}
+
+pub fn toggle_stats_panel(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut stats_panel: ResMut<StatsPanel>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::I) {
+        stats_panel.open = !stats_panel.open;
+    }
+}
+
+pub fn show_stats_panel(
+    mut contexts: EguiContexts,
+    stats_panel: Res<StatsPanel>,
+    models: Query<(&SceneHandle, &ModelStats)>,
+) {
+    if !stats_panel.open || models.is_empty() {
+        return;
+    }
+
+    egui::Window::new("Statistics")
+        .default_pos([10.0, 300.0])
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            for (scene_handle, stats) in &models {
+                egui::CollapsingHeader::new(scene_handle.label())
+                    .id_source(scene_handle.path())
+                    .default_open(true)
+                    .show(ui, |ui| stats_ui(ui, stats));
+            }
+        });
+}
+
+fn stats_ui(ui: &mut egui::Ui, stats: &ModelStats) {
+    egui::Grid::new(("stats", &stats.path))
+        .num_columns(2)
+        .show(ui, |ui| {
+            let mut row = |name: &str, value: String| {
+                ui.label(name);
+                ui.label(value);
+                ui.end_row();
+            };
+            row("Scene", stats.scene_index.to_string());
+            row("Nodes", stats.nodes.to_string());
+            row(
+                "Meshes",
+                format!("{} ({} primitives)", stats.meshes, stats.primitives),
+            );
+            row("Triangles", stats.triangles.to_string());
+            row("Vertices", stats.vertices.to_string());
+            row("Materials", stats.materials.to_string());
+            row(
+                "Textures",
+                stats
+                    .textures
+                    .iter()
+                    .map(|texture| format!("{}x{}", texture.x, texture.y))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+            row(
+                "Animations",
+                stats
+                    .animations
+                    .iter()
+                    .map(|animation| {
+                        format!(
+                            "{} {:.2}s",
+                            animation.name.as_deref().unwrap_or("-"),
+                            animation.duration
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+            row(
+                "Skins",
+                format!("{} ({} joints)", stats.skins, stats.joints),
+            );
+            row(
+                "Size",
+                format!(
+                    "{:.3} x {:.3} x {:.3}",
+                    stats.size.x, stats.size.y, stats.size.z
+                ),
+            );
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use crate::scene_setup::tests::{headless_app, load_model};
+    use crate::scene_setup::LoadStatus;
+
+    use super::*;
+
+    #[test]
+    fn textured_quads() {
+        let mut app = headless_app();
+        app.add_asset::<AnimationClip>();
+        let path = "assets/tests/textured_quads.gltf";
+        assert_eq!(load_model(&mut app, path), LoadStatus::Loaded);
+        let gltf_handle = app
+            .world
+            .query::<&SceneHandle>()
+            .single(&app.world)
+            .gltf()
+            .clone();
+
+        let mut assets = SystemState::<StatsAssets>::new(&mut app.world);
+        let stats = app
+            .world
+            .resource_scope(|world, mut scenes: Mut<Assets<Scene>>| {
+                let gltf = world.resource::<Assets<Gltf>>().get(&gltf_handle).unwrap();
+                let scene = scenes.get_mut(&gltf.scenes[0]).unwrap();
+                ModelStats::new(path, 0, gltf, scene, &assets.get(world), Vec3::ONE)
+            });
+
+        // two nodes draw the quad and one the triangle
+        assert_eq!(stats.nodes, 3);
+        assert_eq!(stats.meshes, 2);
+        assert_eq!(stats.primitives, 2);
+        assert_eq!(stats.vertices, 4 + 4 + 3);
+        assert_eq!(stats.triangles, 2 + 2 + 1);
+        assert_eq!(stats.materials, 2);
+        // both materials use the first texture, it is only counted once
+        assert_eq!(stats.textures, vec![UVec2::new(2, 2), UVec2::new(4, 1)]);
+        assert!(stats.animations.is_empty());
+        assert_eq!((stats.skins, stats.joints), (0, 0));
+    }
+}
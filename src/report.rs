@@ -0,0 +1,328 @@
+//! Machine readable output of the validation results and model statistics, for asset pipelines.
+//!
+//! Both formats have one record per model with the same fields in the same order. Fields are only
+//! ever added at the end, and `REPORT_SCHEMA_VERSION` goes up when one changes meaning or goes away.
+
+use serde::Serialize;
+
+use crate::model_stats::ModelStats;
+use crate::validate::{ModelReport, Severity};
+
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+pub const CSV_COLUMNS: [&str; 18] = [
+    "path",
+    "status",
+    "scene",
+    "nodes",
+    "meshes",
+    "primitives",
+    "triangles",
+    "vertices",
+    "materials",
+    "textures",
+    "animations",
+    "skins",
+    "joints",
+    "size_x",
+    "size_y",
+    "size_z",
+    "errors",
+    "warnings",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(ReportFormat::Json),
+            "csv" => Some(ReportFormat::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn write(&self, reports: &[ModelReport]) -> String {
+        match self {
+            ReportFormat::Json => json_report(reports),
+            ReportFormat::Csv => csv_report(reports),
+        }
+    }
+}
+
+fn status(report: &ModelReport) -> &'static str {
+    if report.has_errors() {
+        "failed"
+    } else {
+        "ok"
+    }
+}
+
+fn messages(report: &ModelReport, severity: Severity) -> Vec<&str> {
+    report
+        .issues
+        .iter()
+        .filter(|issue| issue.severity == severity)
+        .map(|issue| issue.message.as_str())
+        .collect()
+}
+
+/// A model's record in the JSON report, the statistics are `null` for models that didn't load.
+#[derive(Serialize)]
+struct JsonModel<'a> {
+    path: &'a str,
+    status: &'static str,
+    scene: Option<usize>,
+    nodes: Option<usize>,
+    meshes: Option<usize>,
+    primitives: Option<usize>,
+    triangles: Option<usize>,
+    vertices: Option<usize>,
+    materials: Option<usize>,
+    /// Width and height of each texture.
+    textures: Option<Vec<[u32; 2]>>,
+    animations: Option<Vec<JsonAnimation<'a>>>,
+    skins: Option<usize>,
+    joints: Option<usize>,
+    size: Option<[Option<f64>; 3]>,
+    errors: Vec<&'a str>,
+    warnings: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonAnimation<'a> {
+    name: Option<&'a str>,
+    duration: Option<f64>,
+}
+
+impl<'a> JsonModel<'a> {
+    fn new(report: &'a ModelReport) -> Self {
+        let stats = report.stats.as_ref();
+        let count = |value: fn(&ModelStats) -> usize| stats.map(value);
+        JsonModel {
+            path: &report.path,
+            status: status(report),
+            scene: count(|stats| stats.scene_index),
+            nodes: count(|stats| stats.nodes),
+            meshes: count(|stats| stats.meshes),
+            primitives: count(|stats| stats.primitives),
+            triangles: count(|stats| stats.triangles),
+            vertices: count(|stats| stats.vertices),
+            materials: count(|stats| stats.materials),
+            textures: stats.map(|stats| {
+                stats
+                    .textures
+                    .iter()
+                    .map(|texture| texture.to_array())
+                    .collect()
+            }),
+            animations: stats.map(|stats| {
+                stats
+                    .animations
+                    .iter()
+                    .map(|animation| JsonAnimation {
+                        name: animation.name.as_deref(),
+                        duration: rounded(animation.duration),
+                    })
+                    .collect()
+            }),
+            skins: count(|stats| stats.skins),
+            joints: count(|stats| stats.joints),
+            size: stats.map(|stats| stats.size.to_array().map(rounded)),
+            errors: messages(report, Severity::Error),
+            warnings: messages(report, Severity::Warning),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema_version: u32,
+    models: Vec<JsonModel<'a>>,
+}
+
+/// `{"schema_version": 1, "models": [...]}`, with the statistics `null` for models that didn't load.
+pub fn json_report(reports: &[ModelReport]) -> String {
+    let report = JsonReport {
+        schema_version: REPORT_SCHEMA_VERSION,
+        models: reports.iter().map(JsonModel::new).collect(),
+    };
+    serde_json::to_string_pretty(&report).expect("reports only hold strings and numbers") + "\n"
+}
+
+/// A header row of `CSV_COLUMNS` and a row per model. Lists are joined with `;`, textures are
+/// `WxH` and animations `name:seconds`. Statistics are empty for models that didn't load.
+pub fn csv_report(reports: &[ModelReport]) -> String {
+    let mut csv = CSV_COLUMNS.join(",");
+    csv.push('\n');
+
+    for report in reports {
+        let mut row = vec![report.path.clone(), status(report).to_string()];
+        match &report.stats {
+            Some(stats) => row.extend([
+                stats.scene_index.to_string(),
+                stats.nodes.to_string(),
+                stats.meshes.to_string(),
+                stats.primitives.to_string(),
+                stats.triangles.to_string(),
+                stats.vertices.to_string(),
+                stats.materials.to_string(),
+                stats
+                    .textures
+                    .iter()
+                    .map(|texture| format!("{}x{}", texture.x, texture.y))
+                    .collect::<Vec<_>>()
+                    .join(";"),
+                stats
+                    .animations
+                    .iter()
+                    .map(|animation| {
+                        format!(
+                            "{}:{}",
+                            animation.name.as_deref().unwrap_or(""),
+                            csv_number(animation.duration)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(";"),
+                stats.skins.to_string(),
+                stats.joints.to_string(),
+                csv_number(stats.size.x),
+                csv_number(stats.size.y),
+                csv_number(stats.size.z),
+            ]),
+            // everything but the path, status, errors and warnings
+            None => row.extend(std::iter::repeat_n(String::new(), CSV_COLUMNS.len() - 4)),
+        }
+        row.push(messages(report, Severity::Error).join(";"));
+        row.push(messages(report, Severity::Warning).join(";"));
+
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Rounded to a micrometer so the output doesn't change with float noise. JSON has no NaN or
+/// infinity, those are `None`.
+pub fn rounded(value: f32) -> Option<f64> {
+    if !value.is_finite() {
+        return None;
+    }
+    let rounded = (value as f64 * 1e6).round() / 1e6;
+    // no "-0"
+    Some(if rounded == 0.0 { 0.0 } else { rounded })
+}
+
+/// Empty for NaN and infinity.
+fn csv_number(value: f32) -> String {
+    rounded(value).map_or_else(String::new, |value| value.to_string())
+}
+
+/// Quoted when it holds a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{UVec2, Vec3};
+
+    use crate::model_stats::AnimationStats;
+    use crate::validate::Issue;
+
+    use super::*;
+
+    fn issue(severity: Severity, message: &str) -> Issue {
+        Issue {
+            severity,
+            message: message.to_string(),
+        }
+    }
+
+    /// A model that loaded with a warning, one that failed, and one with sizes that need rounding.
+    fn fixture_reports() -> Vec<ModelReport> {
+        vec![
+            ModelReport {
+                path: "assets/models/alien.glb".to_string(),
+                issues: vec![issue(Severity::Warning, "mesh 'Body' has no tangents")],
+                stats: Some(ModelStats {
+                    path: "assets/models/alien.glb".to_string(),
+                    scene_index: 0,
+                    nodes: 12,
+                    meshes: 3,
+                    primitives: 4,
+                    triangles: 1280,
+                    vertices: 950,
+                    materials: 2,
+                    textures: vec![UVec2::new(512, 512), UVec2::new(256, 128)],
+                    animations: vec![
+                        AnimationStats {
+                            name: Some("Walk".to_string()),
+                            duration: 1.25,
+                        },
+                        AnimationStats {
+                            name: None,
+                            duration: 0.5,
+                        },
+                    ],
+                    skins: 1,
+                    joints: 18,
+                    size: Vec3::new(1.0, 2.5, 0.75),
+                }),
+            },
+            ModelReport {
+                path: "assets/models/broken, \"copy\".glb".to_string(),
+                issues: vec![
+                    issue(Severity::Error, "file not found"),
+                    issue(Severity::Warning, "no scenes; nothing to show"),
+                ],
+                stats: None,
+            },
+            ModelReport {
+                path: "assets/models/tile.glb".to_string(),
+                issues: Vec::new(),
+                stats: Some(ModelStats {
+                    path: "assets/models/tile.glb".to_string(),
+                    scene_index: 1,
+                    nodes: 1,
+                    meshes: 1,
+                    primitives: 1,
+                    triangles: 2,
+                    vertices: 4,
+                    materials: 0,
+                    textures: Vec::new(),
+                    animations: Vec::new(),
+                    skins: 0,
+                    joints: 0,
+                    size: Vec3::new(0.1 + 0.2, -0.0000001, f32::NAN),
+                }),
+            },
+        ]
+    }
+
+    #[test]
+    fn json_matches_the_golden_file() {
+        assert_eq!(
+            json_report(&fixture_reports()),
+            include_str!("../assets/tests/report.json")
+        );
+    }
+
+    #[test]
+    fn csv_matches_the_golden_file() {
+        assert_eq!(
+            csv_report(&fixture_reports()),
+            include_str!("../assets/tests/report.csv")
+        );
+    }
+}
@@ -1,69 +1,394 @@
-//! A simple glTF scene viewer made with Bevy.
+//! Loading the models given on the command line, following each one until its scene is spawned,
+//! and setting up the camera and light once they are all in place.
 //!
-//! Just run `cargo run --release --example scene_viewer /path/to/model.gltf#Scene0`,
-//! replacing the path as appropriate.
-//! With no arguments it will load the `FieldHelmet` glTF model from the repository assets subdirectory.
+//! Run `cargo run -- path/to/model.gltf#Scene0`, with paths relative to the crate folder. With no
+//! arguments the viewer loads `assets/models/alien.glb`; see `--help` for the rest.
 
 use std::f32::consts::PI;
 use bevy::{
     asset::LoadState,
     gltf::Gltf,
-    math::Vec3A,
     prelude::*,
-    render::primitives::{Aabb, Sphere},
+    render::primitives::Aabb,
     scene::InstanceId,
+    tasks::{IoTaskPool, Task},
+    window::PrimaryWindow,
 };
 use bevy::pbr::CascadeShadowConfigBuilder;
-use crate::camera_pan_orbit::spawn_camera;
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use futures_lite::future;
+use bevy_inspector_egui::egui;
+use crate::args::{CameraMode, ModelPath, SceneSelector, ViewerArgs};
+use crate::bounds::{BoundsBuilder, SceneBounds, transform_aabb};
+use crate::camera::spawn_fly_camera;
+use crate::camera_pan_orbit::{frame_bounds, spawn_camera};
+use crate::model_grid::ModelGrid;
+use crate::thumbnails::ThumbnailBatch;
 
 // use crate::CameraController;
 
 // #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
 // struct CameraControllerCheckSystem;
 
-#[derive(Resource)]
+/// Where the glTF file is in its journey from disk to a spawned scene.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum LoadStatus {
+    /// The asset server is still loading the file.
+    #[default]
+    Loading,
+    /// The file has loaded and the scene instance is being spawned.
+    Spawning,
+    /// The scene instance is ready.
+    Loaded,
+    /// The file could not be loaded or has nothing to spawn.
+    Failed { path: String, reason: String },
+}
+
+/// One loaded glTF file. Lives on the root entity its scene instance is spawned under, so moving or
+/// hiding that entity moves or hides the whole model.
+#[derive(Component)]
 pub struct SceneHandle {
     handle: Handle<Gltf>,
+    path: String,
+    /// The scene asked for on the command line.
+    requested_scene: Option<SceneSelector>,
+    /// The scene that is, or is about to be, spawned. Resolved once the glTF file has loaded.
+    scene_index: Option<usize>,
+    scene_count: usize,
     #[cfg(feature = "animation")]
     animations: Vec<Handle<AnimationClip>>,
     instance_id: Option<InstanceId>,
-    is_loaded: bool,
+    status: LoadStatus,
+    /// Works out why the asset server failed to load the file, see `diagnose_load_failure`.
+    diagnosis: Option<Task<String>>,
     has_light: bool,
+    /// Bounds of the spawned scene relative to the root entity, see `arrange_models`.
+    bounds: Option<Aabb>,
+}
+
+impl SceneHandle {
+    pub fn new(handle: Handle<Gltf>, path: &str, requested_scene: Option<SceneSelector>) -> Self {
+        SceneHandle {
+            handle,
+            path: path.to_string(),
+            requested_scene,
+            scene_index: None,
+            scene_count: 0,
+            #[cfg(feature = "animation")]
+            animations: Vec::new(),
+            instance_id: None,
+            status: LoadStatus::Loading,
+            diagnosis: None,
+            has_light: false,
+            bounds: None,
+        }
+    }
+
+    pub fn gltf(&self) -> &Handle<Gltf> {
+        &self.handle
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// A short name for the model, its file name without the extension.
+    pub fn label(&self) -> String {
+        std::path::Path::new(&self.path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.clone())
+    }
+
+    /// The scene that is, or is about to be, spawned.
+    pub fn scene_index(&self) -> Option<usize> {
+        self.scene_index
+    }
+
+    pub fn instance_id(&self) -> Option<InstanceId> {
+        self.instance_id
+    }
+
+    pub fn bounds(&self) -> Option<&Aabb> {
+        self.bounds.as_ref()
+    }
+
+    pub fn set_bounds(&mut self, bounds: Aabb) {
+        self.bounds = Some(bounds);
+    }
+
+    pub fn status(&self) -> &LoadStatus {
+        &self.status
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.status == LoadStatus::Loaded
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.status, LoadStatus::Failed { .. })
+    }
+
+    fn fail(&mut self, reason: String) {
+        error!("Failed to load {}: {}", self.path, reason);
+        self.status = LoadStatus::Failed {
+            path: self.path.clone(),
+            reason,
+        };
+    }
 }
 
-pub fn setup_scene(mut commands: Commands<'_, '_>, asset_server: Res<AssetServer>) {
-
-    let scene_path = std::env::args()
-        .nth(1)
-        // .unwrap_or_else(|| "assets/models/FlightHelmet/FlightHelmet.gltf".to_string());
-        // .unwrap_or_else(|| "assets/models/monkey/Monkey.gltf".to_string());
-        // .unwrap_or_else(|| "assets/craft_speederA.gltf".to_string());
-        .unwrap_or_else(|| "assets/models/alien.glb".to_string());
-
-    info!("Loading {}", scene_path);
-
-    commands.insert_resource(SceneHandle {
-        handle: asset_server.load(&scene_path),
-        #[cfg(feature = "animation")]
-        animations: Vec::new(),
-        instance_id: None,
-        is_loaded: false,
-        has_light: false,
-    });
+/// Replaces whatever models are shown with the given ones.
+pub struct LoadModels(pub Vec<ModelPath>);
+
+/// Whether `setup_scene_after_load` has set up the camera and light for the current models.
+#[derive(Resource, Default)]
+pub struct SceneSetup {
+    done: bool,
+}
+
+impl SceneSetup {
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Set up the camera and light again once the next models have loaded.
+    pub fn reset(&mut self) {
+        self.done = false;
+    }
+}
+
+/// Tags the camera and light spawned by `setup_scene_after_load`, so they can be replaced when
+/// other models are loaded.
+#[derive(Component)]
+pub struct SceneSetupEntity;
+
+pub fn setup_scene(
+    viewer_args: Res<ViewerArgs>,
+    thumbnail_batch: Option<Res<ThumbnailBatch>>,
+    mut load_models: EventWriter<LoadModels>,
+) {
+    // the batch loads the models one at a time itself
+    if thumbnail_batch.is_none() {
+        load_models.send(LoadModels(viewer_args.models.clone()));
+    }
+}
+
+/// Despawns the current models along with their camera and light, then spawns a `SceneHandle`
+/// for each of the new models. `scene_load_check` and `setup_scene_after_load` take it from there.
+#[allow(clippy::too_many_arguments)]
+pub fn load_models(
+    mut commands: Commands,
+    mut events: EventReader<LoadModels>,
+    asset_server: Res<AssetServer>,
+    mut scene_spawner: ResMut<SceneSpawner>,
+    scene_handles: Query<(Entity, &SceneHandle)>,
+    setup_entities: Query<Entity, With<SceneSetupEntity>>,
+    mut model_grid: ResMut<ModelGrid>,
+    mut scene_setup: ResMut<SceneSetup>,
+    mut scene_bounds: ResMut<SceneBounds>,
+) {
+    // only the latest request matters
+    let Some(LoadModels(models)) = events.iter().last() else {
+        return;
+    };
+
+    for (entity, scene_handle) in &scene_handles {
+        if let Some(instance_id) = scene_handle.instance_id {
+            scene_spawner.despawn_instance(instance_id);
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+    for entity in &setup_entities {
+        commands.entity(entity).despawn_recursive();
+    }
+    model_grid.rearrange();
+    scene_setup.reset();
+    scene_bounds.set(None);
+
+    for model in models {
+        for scene_path in expand_wildcards(&model.path) {
+            info!("Loading {}", scene_path);
+
+            let scene_handle = SceneHandle::new(
+                asset_server.load(&scene_path),
+                &scene_path,
+                model.scene.clone(),
+            );
+            commands.spawn((
+                SpatialBundle::default(),
+                Name::new(scene_handle.label()),
+                scene_handle,
+            ));
+        }
+    }
+}
+
+/// Expands `*` and `?` in the file name part of `path` to the matching files in the asset folder,
+/// sorted by name, and a folder to the models in it. Paths without wildcards, or with no matches,
+/// are returned as they are.
+pub fn expand_wildcards(path: &str) -> Vec<String> {
+    let path = path.trim_end_matches('/');
+    if std::path::Path::new(&asset_folder()).join(path).is_dir() {
+        let files = model_files(path);
+        if files.is_empty() {
+            warn!("No models in {}", path);
+            return vec![path.to_string()];
+        }
+        return files;
+    }
+
+    let (dir, pattern) = match path.rsplit_once('/') {
+        Some((dir, pattern)) => (Some(dir), pattern),
+        None => (None, path),
+    };
+    if !pattern.contains(['*', '?']) {
+        return vec![path.to_string()];
+    }
+
+    let folder = std::path::Path::new(&asset_folder()).join(dir.unwrap_or("."));
+    let mut matches: Vec<String> = std::fs::read_dir(folder)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| wildcard_match(pattern, name))
+        .map(|name| match dir {
+            Some(dir) => format!("{}/{}", dir, name),
+            None => name,
+        })
+        .collect();
+
+    if matches.is_empty() {
+        warn!("No files match {}", path);
+        return vec![path.to_string()];
+    }
+    matches.sort();
+    matches
+}
+
+/// The glTF and glb files in a folder of the asset folder, as sorted asset paths.
+pub fn model_files(dir: &str) -> Vec<String> {
+    let folder = std::path::Path::new(&asset_folder()).join(dir);
+    let mut files: Vec<String> = std::fs::read_dir(folder)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| is_model_file(name))
+        .map(|name| format!("{}/{}", dir, name))
+        .collect();
+    files.sort();
+    files
+}
+
+pub fn is_model_file(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.ends_with(".glb") || name.ends_with(".gltf")
+}
+
+/// Matches `name` against a pattern where `*` is any run of characters and `?` any one character.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // classic two pointer match, backtracking to the last '*' on a mismatch
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// The folder asset paths are relative to.
+pub fn asset_folder() -> String {
+    std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string())
 }
 
 pub fn scene_load_check(
     asset_server: Res<AssetServer>,
     mut scenes: ResMut<Assets<Scene>>,
     gltf_assets: ResMut<Assets<Gltf>>,
-    mut scene_handle: ResMut<SceneHandle>,
+    mut scene_handles: Query<(Entity, &mut SceneHandle)>,
     mut scene_spawner: ResMut<SceneSpawner>,
+) {
+    for (entity, mut scene_handle) in &mut scene_handles {
+        if scene_handle.is_failed() {
+            continue;
+        }
+        load_check(
+            entity,
+            &mut scene_handle,
+            &asset_server,
+            &mut scenes,
+            &gltf_assets,
+            &mut scene_spawner,
+        );
+    }
+}
+
+fn load_check(
+    entity: Entity,
+    scene_handle: &mut SceneHandle,
+    asset_server: &AssetServer,
+    scenes: &mut Assets<Scene>,
+    gltf_assets: &Assets<Gltf>,
+    scene_spawner: &mut SceneSpawner,
 ) {
     match scene_handle.instance_id {
         None => {
-            if asset_server.get_load_state(&scene_handle.handle) == LoadState::Loaded {
+            let load_state = asset_server.get_load_state(&scene_handle.handle);
+
+            if load_state == LoadState::Failed {
+                // re-reading the file can take a while, the status stays `Loading` until it's done
+                match &mut scene_handle.diagnosis {
+                    None => {
+                        let path = scene_handle.path.clone();
+                        scene_handle.diagnosis = Some(
+                            IoTaskPool::get().spawn(async move { diagnose_load_failure(&path) }),
+                        );
+                    }
+                    Some(diagnosis) => {
+                        if let Some(reason) = future::block_on(future::poll_once(diagnosis)) {
+                            scene_handle.diagnosis = None;
+                            scene_handle.fail(reason);
+                        }
+                    }
+                }
+            } else if load_state == LoadState::Loaded {
                 let gltf = gltf_assets.get(&scene_handle.handle).unwrap();
-                let gltf_scene_handle = gltf.scenes.first().expect("glTF file contains no scenes!");
+
+                if gltf.scenes.is_empty() {
+                    scene_handle.fail("glTF file contains no scenes".to_string());
+                    return;
+                }
+
+                let scene_index = match scene_handle.scene_index {
+                    Some(scene_index) => scene_index,
+                    None => {
+                        info!("{}:", scene_handle.path);
+                        list_scenes(gltf);
+                        let scene_index =
+                            select_scene(gltf, scene_handle.requested_scene.as_ref());
+                        scene_handle.scene_count = gltf.scenes.len();
+                        scene_handle.scene_index = Some(scene_index);
+                        scene_index
+                    }
+                };
+                let gltf_scene_handle = &gltf.scenes[scene_index];
                 let scene = scenes.get_mut(gltf_scene_handle).unwrap();
 
                 let mut query = scene
@@ -78,7 +403,7 @@ pub fn scene_load_check(
                         });
 
                 scene_handle.instance_id =
-                    Some(scene_spawner.spawn(gltf_scene_handle.clone_weak()));
+                    Some(scene_spawner.spawn_as_child(gltf_scene_handle.clone_weak(), entity));
 
                 #[cfg(feature = "animation")]
                 {
@@ -96,27 +421,166 @@ pub fn scene_load_check(
                     }
                 }
 
-                info!("Spawning scene...");
+                scene_handle.status = LoadStatus::Spawning;
+                info!("Spawning scene {} of {}...", scene_index, scene_handle.path);
             }
         }
-        Some(instance_id) if !scene_handle.is_loaded => {
+        Some(instance_id) if scene_handle.status == LoadStatus::Spawning => {
             if scene_spawner.instance_is_ready(instance_id) {
                 info!("...done!");
-                scene_handle.is_loaded = true;
+                scene_handle.status = LoadStatus::Loaded;
             }
         }
         Some(_) => {}
     }
 }
 
+/// The asset server only logs why a load failed, so work out a reason that can be shown to the user
+/// by checking the file ourselves.
+fn diagnose_load_failure(path: &str) -> String {
+    let full_path = std::path::Path::new(&asset_folder()).join(path);
+
+    if !full_path.is_file() {
+        return format!("file not found at {}", full_path.display());
+    }
+
+    match gltf::import(&full_path) {
+        Err(error) => error.to_string(),
+        Ok(_) => "the asset loader rejected the file, see the log for details".to_string(),
+    }
+}
+
+/// Finds the index of the scene to spawn, falling back to the file's default scene and then to the
+/// first scene when nothing, or something that doesn't exist, was requested.
+pub fn select_scene(gltf: &Gltf, requested: Option<&SceneSelector>) -> usize {
+    let position = |handle: &Handle<Scene>| gltf.scenes.iter().position(|scene| scene == handle);
+
+    let requested_index = match requested {
+        Some(SceneSelector::Index(index)) if *index < gltf.scenes.len() => Some(*index),
+        Some(SceneSelector::Name(name)) => gltf.named_scenes.get(name).and_then(position),
+        _ => None,
+    };
+    if requested_index.is_none() {
+        if let Some(requested) = requested {
+            warn!("Scene {:?} not found, using the default scene", requested);
+        }
+    }
+
+    requested_index
+        .or_else(|| gltf.default_scene.as_ref().and_then(position))
+        .unwrap_or(0)
+}
+
+fn scene_name(gltf: &Gltf, index: usize) -> Option<&str> {
+    gltf.named_scenes
+        .iter()
+        .find(|(_, handle)| **handle == gltf.scenes[index])
+        .map(|(name, _)| name.as_str())
+}
+
+fn list_scenes(gltf: &Gltf) {
+    info!(
+        "Found {} scene{}:",
+        gltf.scenes.len(),
+        if gltf.scenes.len() == 1 { "" } else { "s" }
+    );
+    for (index, handle) in gltf.scenes.iter().enumerate() {
+        let is_default = gltf.default_scene.as_ref() == Some(handle);
+        info!(
+            "    Scene{} {}{}",
+            index,
+            scene_name(gltf, index).unwrap_or(""),
+            if is_default { " (default)" } else { "" }
+        );
+    }
+}
+
+/// Despawns the current scene instances and moves on to the next scene in each file when Tab is
+/// pressed. `scene_load_check` then spawns the new scenes.
+pub fn switch_scene(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut scene_handles: Query<&mut SceneHandle>,
+    mut scene_spawner: ResMut<SceneSpawner>,
+    mut model_grid: ResMut<ModelGrid>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() || !keyboard_input.just_pressed(KeyCode::Tab) {
+        return;
+    }
+
+    for mut scene_handle in &mut scene_handles {
+        if !scene_handle.is_loaded() || scene_handle.scene_count < 2 {
+            continue;
+        }
+
+        if let (Some(instance_id), Some(scene_index)) =
+            (scene_handle.instance_id, scene_handle.scene_index)
+        {
+            scene_spawner.despawn_instance(instance_id);
+            scene_handle.scene_index = Some((scene_index + 1) % scene_handle.scene_count);
+            scene_handle.instance_id = None;
+            scene_handle.status = LoadStatus::Spawning;
+            scene_handle.bounds = None;
+            // the new scene has its own size, so the grid has to be laid out again
+            model_grid.rearrange();
+        }
+    }
+}
+
+/// Replaces the loaded model with the next (PageDown) or previous (PageUp) glTF or glb file in the
+/// same folder, wrapping around at either end.
+pub fn step_through_directory(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    scene_handles: Query<&SceneHandle>,
+    mut load_models: EventWriter<LoadModels>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    let step: isize = if keyboard_input.just_pressed(KeyCode::PageDown) {
+        1
+    } else if keyboard_input.just_pressed(KeyCode::PageUp) {
+        -1
+    } else {
+        return;
+    };
+    let Some(scene_handle) = scene_handles.iter().next() else {
+        return;
+    };
+
+    let (dir, name) = match scene_handle.path.rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, scene_handle.path.as_str()),
+    };
+    let files = model_files(dir.unwrap_or("."));
+    if files.is_empty() {
+        return;
+    }
+
+    let file_name = |path: &str| path.rsplit('/').next().unwrap_or(path).to_string();
+    let next = match files.iter().position(|path| file_name(path) == name) {
+        Some(index) => (index as isize + step).rem_euclid(files.len() as isize) as usize,
+        None => 0,
+    };
+    info!("Model {} of {} in {}", next + 1, files.len(), dir.unwrap_or("."));
+
+    let next_name = file_name(&files[next]);
+    let path = match dir {
+        Some(dir) => format!("{}/{}", dir, next_name),
+        None => next_name,
+    };
+    load_models.send(LoadModels(vec![ModelPath { path, scene: None }]));
+}
+
 #[cfg(feature = "animation")]
-fn start_animation(
+pub fn start_animation(
     mut player: Query<&mut AnimationPlayer>,
     mut done: Local<bool>,
-    scene_handle: Res<SceneHandle>,
+    scene_handles: Query<&SceneHandle>,
 ) {
     if !*done {
-        if let Ok(mut player) = player.get_single_mut() {
+        if let (Ok(mut player), Some(scene_handle)) = (player.get_single_mut(), scene_handles.iter().next()) {
             if let Some(animation) = scene_handle.animations.first() {
                 player.play(animation.clone_weak()).repeat();
                 *done = true;
@@ -126,13 +590,16 @@ fn start_animation(
 }
 
 #[cfg(feature = "animation")]
-fn keyboard_animation_control(
+pub fn keyboard_animation_control(
     keyboard_input: Res<Input<KeyCode>>,
     mut animation_player: Query<&mut AnimationPlayer>,
-    scene_handle: Res<SceneHandle>,
+    scene_handles: Query<&SceneHandle>,
     mut current_animation: Local<usize>,
     mut changing: Local<bool>,
 ) {
+    let Some(scene_handle) = scene_handles.iter().next() else {
+        return;
+    };
     if scene_handle.animations.is_empty() {
         return;
     }
@@ -164,118 +631,197 @@ fn keyboard_animation_control(
     }
 }
 
+/// Shows a window with the reasons when glTF files could not be loaded.
+pub fn show_load_status(mut contexts: EguiContexts, scene_handles: Query<&SceneHandle>) {
+    let failures: Vec<_> = scene_handles
+        .iter()
+        .filter_map(|scene_handle| match scene_handle.status() {
+            LoadStatus::Failed { path, reason } => Some((path, reason)),
+            _ => None,
+        })
+        .collect();
+    if failures.is_empty() {
+        return;
+    }
+
+    egui::Window::new("Load failed")
+        .anchor(egui::Align2::CENTER_TOP, [0.0, 10.0])
+        .collapsible(false)
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            for (path, reason) in failures {
+                ui.label(format!("Could not load {}", path));
+                ui.colored_label(egui::Color32::LIGHT_RED, reason);
+            }
+        });
+}
+
 pub fn setup_scene_after_load(
     mut commands: Commands,
-    mut setup: Local<bool>,
-    mut scene_handle: ResMut<SceneHandle>,
-    meshes: Query<(&GlobalTransform, Option<&Aabb>), With<Handle<Mesh>>>,
+    mut scene_setup: ResMut<SceneSetup>,
+    mut scene_bounds: ResMut<SceneBounds>,
+    scene_handles: Query<(&SceneHandle, &Transform)>,
+    model_grid: Res<ModelGrid>,
+    viewer_args: Res<ViewerArgs>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
 ) {
-    if scene_handle.is_loaded && !*setup {
-        *setup = true;
-        // Find an approximate bounding box of the scene from its meshes
-        if meshes.iter().any(|(_, maybe_aabb)| maybe_aabb.is_none()) {
-            return;
-        }
+    if scene_setup.done || !model_grid.is_arranged() {
+        return;
+    }
+
+    let aspect_ratio = primary_window
+        .get_single()
+        .map(|window| window.width() / window.height())
+        .unwrap_or(1.0);
+    let fov = PerspectiveProjection::default().fov;
 
-        let mut min = Vec3A::splat(f32::MAX);
-        let mut max = Vec3A::splat(f32::MIN);
-
-        for (transform, maybe_aabb) in &meshes {
-            let aabb = maybe_aabb.unwrap();
-            // If the Aabb had not been rotated, applying the non-uniform scale would produce the
-            // correct bounds. However, it could very well be rotated and so we first convert to
-            // a Sphere, and then back to an Aabb to find the conservative min and max points.
-            let sphere = Sphere {
-                center: Vec3A::from(transform.transform_point(Vec3::from(aabb.center))),
-                radius: transform.radius_vec3a(aabb.half_extents),
-            };
-            let aabb = Aabb::from(sphere);
-            min = min.min(aabb.min());
-            max = max.max(aabb.max());
+    if scene_handles.iter().all(|(scene_handle, _)| scene_handle.is_failed()) {
+        scene_setup.done = true;
+        // Nothing to frame, but keep a camera around so the viewer stays usable
+        let framing = frame_bounds(Vec3::ZERO, Vec3::ONE, fov, aspect_ratio);
+        let camera = match viewer_args.camera_mode {
+            CameraMode::Orbit => spawn_camera(&mut commands, &framing),
+            CameraMode::Fly => spawn_fly_camera(&mut commands, &framing),
+        };
+        commands.entity(camera).insert(SceneSetupEntity);
+        return;
+    }
+
+    scene_setup.done = true;
+    // Combine the bounds of every model where the grid placed it
+    let mut builder = BoundsBuilder::default();
+    for (scene_handle, transform) in &scene_handles {
+        if let Some(aabb) = scene_handle.bounds() {
+            builder.add_aabb(&transform_aabb(aabb, &transform.compute_matrix()));
         }
+    }
+    // nothing with a mesh was loaded when the builder is empty
+    let aabb = builder
+        .build()
+        .unwrap_or_else(|| Aabb::from_min_max(Vec3::ZERO, Vec3::ZERO));
+    scene_bounds.set(Some(aabb));
 
-        let size = (max - min).length();
-        let aabb = Aabb::from_min_max(Vec3::from(min), Vec3::from(max));
-
-        spawn_camera(&mut commands);
-
-        // info!("Spawning a controllable 3D perspective camera");
-        //
-        // let mut projection = PerspectiveProjection::default();
-        //
-        // projection.far = projection.far.max(size * 10.0);
-        //
-        // let mut camera_controller = CameraController::default();
-        // camera_controller.target = Vec3::from(aabb.center);
-        //
-        // commands.spawn((
-        //     Camera3dBundle {
-        //         projection: projection.into(),
-        //         transform: Transform::from_translation(
-        //             Vec3::from(aabb.center) + size * Vec3::new(0.5, 0.25, 0.5),
-        //         ).looking_at(Vec3::from(aabb.center), Vec3::Y),
-        //         camera: Camera {
-        //             is_active: true,
-        //             ..default()
-        //         },
-        //         ..default()
-        //     },
-        //     camera_controller,
-        // ));
-
-        // Spawn a default light if the scene does not have one
-        if !scene_handle.has_light {
-            let sphere = Sphere {
-                center: aabb.center,
-                radius: aabb.half_extents.length(),
-            };
-            let aabb = Aabb::from(sphere);
-            let min = aabb.min();
-            let max = aabb.max();
-
-            info!("Spawning a directional light");
-            // commands.spawn(DirectionalLightBundle {
-            //     directional_light: DirectionalLight {
-            //         shadow_projection: OrthographicProjection {
-            //             left: min.x,
-            //             right: max.x,
-            //             bottom: min.y,
-            //             top: max.y,
-            //             near: min.z,
-            //             far: max.z,
-            //             ..default()
-            //         },
-            //         shadows_enabled: false,
-            //         ..default()
-            //     },
-            //     ..default()
-            // });
-
-            // directional 'sun' light
-            commands.spawn(DirectionalLightBundle {
-                directional_light: DirectionalLight {
-                    shadows_enabled: true,
-                    ..default()
-                },
-                transform: Transform {
-                    translation: Vec3::new(0.0, 2.0, 0.0),
-                    rotation: Quat::from_rotation_x(-PI / 4.),
-                    ..default()
-                },
-                // The default cascade config is designed to handle large scenes.
-                // As this example has a much smaller world, we can tighten the shadow
-                // bounds for better visual quality.
-                cascade_shadow_config: CascadeShadowConfigBuilder {
-                    first_cascade_far_bound: 4.0,
-                    maximum_distance: 10.0,
-                    ..default()
-                }
-                    .into(),
+    let framing = frame_bounds(
+        Vec3::from(aabb.center),
+        Vec3::from(aabb.half_extents),
+        fov,
+        aspect_ratio,
+    );
+    let camera = match viewer_args.camera_mode {
+        CameraMode::Orbit => spawn_camera(&mut commands, &framing),
+        CameraMode::Fly => spawn_fly_camera(&mut commands, &framing),
+    };
+    commands.entity(camera).insert(SceneSetupEntity);
+
+    // Spawn a default light if the scene does not have one
+    let has_light = scene_handles.iter().any(|(scene_handle, _)| scene_handle.has_light);
+    if !has_light && viewer_args.lighting.has_directional_light() {
+        info!("Spawning a directional light");
+        // directional 'sun' light
+        commands.spawn(DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                shadows_enabled: viewer_args.shadows,
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(0.0, 2.0, 0.0),
+                rotation: Quat::from_rotation_x(-PI / 4.),
+                ..default()
+            },
+            // Fit the cascades to the framed models: the first one ends at the focus and shadows
+            // reach past the far side of the models, with room to zoom out a little.
+            cascade_shadow_config: CascadeShadowConfigBuilder {
+                minimum_distance: framing.near,
+                first_cascade_far_bound: framing.radius,
+                maximum_distance: 2.0 * (framing.radius + aabb.half_extents.length()),
                 ..default()
-            });
+            }
+                .into(),
+            ..default()
+        })
+            .insert(SceneSetupEntity);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use bevy::{
+        gltf::GltfPlugin,
+        render::{mesh::MeshPlugin, texture::ImagePlugin},
+        scene::ScenePlugin,
+    };
+
+    use super::*;
+
+    /// Just enough of Bevy to load a glTF file and spawn its scene, without a window or GPU.
+    pub(crate) fn headless_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin {
+                asset_folder: asset_folder(),
+                watch_for_changes: false,
+            })
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(MeshPlugin)
+            .add_plugin(ImagePlugin::default())
+            .add_asset::<StandardMaterial>()
+            // the types glTF scenes are made of, which the render plugins would register
+            .register_type::<Visibility>()
+            .register_type::<ComputedVisibility>()
+            .register_type::<Handle<Mesh>>()
+            .register_type::<Handle<StandardMaterial>>()
+            .register_type::<Aabb>()
+            .add_plugin(ScenePlugin)
+            .add_plugin(GltfPlugin)
+            .init_resource::<ModelGrid>()
+            .init_resource::<SceneSetup>()
+            .init_resource::<SceneBounds>()
+            .add_event::<LoadModels>()
+            .add_system(load_models)
+            .add_system(scene_load_check);
+        app
+    }
 
-            scene_handle.has_light = true;
+    /// Loads `path` in `app` and updates until it has loaded or failed.
+    pub(crate) fn load_model(app: &mut App, path: &str) -> LoadStatus {
+        app.world.send_event(LoadModels(vec![ModelPath {
+            path: path.to_string(),
+            scene: None,
+        }]));
+        for _ in 0..1000 {
+            app.update();
+            let status = app
+                .world
+                .query::<&SceneHandle>()
+                .single(&app.world)
+                .status()
+                .clone();
+            if matches!(status, LoadStatus::Loaded | LoadStatus::Failed { .. }) {
+                return status;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        panic!("{} did not finish loading", path);
+    }
+
+    fn load_status(path: &str) -> LoadStatus {
+        load_model(&mut headless_app(), path)
+    }
+
+    #[test]
+    fn missing_file_fails() {
+        match load_status("assets/models/does_not_exist.glb") {
+            LoadStatus::Failed { path, reason } => {
+                assert_eq!(path, "assets/models/does_not_exist.glb");
+                assert!(reason.starts_with("file not found"), "{}", reason);
+            }
+            status => panic!("expected Failed, got {:?}", status),
         }
     }
-}
 
+    #[test]
+    fn valid_file_loads() {
+        assert_eq!(load_status("assets/models/alien.glb"), LoadStatus::Loaded);
+    }
+}
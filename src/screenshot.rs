@@ -0,0 +1,487 @@
+//! Saves what the active camera sees to a PNG, at any size and independent of the window.
+//!
+//! A second camera with the active camera's view renders into an image. Once it has rendered a few
+//! frames, a render graph node copies the image into a buffer that is read back after the frame,
+//! and the main world writes it to disk.
+
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::{
+    app::AppExit,
+    core_pipeline::{clear_color::ClearColorConfig, tonemapping::Tonemapping},
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_asset::RenderAssets,
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+        render_resource::{
+            Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout,
+            MapMode, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::{RenderContext, RenderDevice},
+        view::RenderLayers,
+        Extract, ExtractSchedule, RenderApp, RenderSet,
+    },
+    window::PrimaryWindow,
+};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+
+use crate::args::ViewerArgs;
+use crate::scene_setup::SceneSetup;
+
+/// Frames the screenshot camera renders before it is captured, so the pipelines for the new
+/// target have been compiled.
+const WARMUP_FRAMES: u32 = 5;
+
+const SCREENSHOT_NODE: &str = "screenshot";
+
+/// Asks for the active camera's view to be saved.
+#[derive(Debug, Clone)]
+pub struct TakeScreenshot {
+    pub path: PathBuf,
+    /// In pixels, the window size when `None`.
+    pub size: Option<UVec2>,
+    /// Clear to transparent instead of the background color.
+    pub transparent: bool,
+}
+
+/// A screenshot has been written, or failed to be. Sent once for every `TakeScreenshot`.
+#[derive(Debug, Clone)]
+pub struct ScreenshotSaved {
+    pub path: PathBuf,
+    pub error: Option<String>,
+}
+
+/// The pixels of a capture, or why they could not be read, filled in by the render world.
+#[derive(Clone, Default)]
+#[allow(clippy::type_complexity)]
+struct ScreenshotSlot(Arc<Mutex<Option<Result<Vec<u8>, String>>>>);
+
+impl ScreenshotSlot {
+    fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().is_none()
+    }
+}
+
+#[derive(Component)]
+pub struct ScreenshotCamera {
+    request: TakeScreenshot,
+    image: Handle<Image>,
+    size: UVec2,
+    frames_left: u32,
+    slot: ScreenshotSlot,
+}
+
+/// Sets up the render world side of the capture, the systems that ask for and save screenshots are
+/// added with the others.
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TakeScreenshot>()
+            .add_event::<ScreenshotSaved>();
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<ScreenshotCopies>()
+            .add_system(extract_screenshots.in_schedule(ExtractSchedule))
+            .add_system(prepare_screenshot_buffers.in_set(RenderSet::Prepare))
+            .add_system(read_screenshot_buffers.in_set(RenderSet::Cleanup));
+
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(SCREENSHOT_NODE, ScreenshotNode);
+        graph.add_node_edge(
+            bevy::render::main_graph::node::CAMERA_DRIVER,
+            SCREENSHOT_NODE,
+        );
+    }
+}
+
+/// P saves a screenshot next to the viewer, named by the time it was taken.
+pub fn screenshot_key(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    viewer_args: Res<ViewerArgs>,
+    mut take_screenshot: EventWriter<TakeScreenshot>,
+) {
+    if contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::P) {
+        take_screenshot.send(TakeScreenshot {
+            path: PathBuf::from(timestamped_file_name(SystemTime::now())),
+            size: viewer_args.screenshot_size,
+            transparent: viewer_args.transparent,
+        });
+    }
+}
+
+/// Takes the `--screenshot` once the models are loaded and framed, and quits when it is written,
+/// with a non-zero exit status when it could not be.
+pub fn screenshot_on_start(
+    viewer_args: Res<ViewerArgs>,
+    scene_setup: Res<SceneSetup>,
+    mut taken: Local<bool>,
+    mut take_screenshot: EventWriter<TakeScreenshot>,
+    mut screenshot_saved: EventReader<ScreenshotSaved>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    let Some(path) = &viewer_args.screenshot else {
+        return;
+    };
+    let path = PathBuf::from(path);
+    if *taken {
+        if let Some(saved) = screenshot_saved.iter().find(|saved| saved.path == path) {
+            if saved.error.is_some() {
+                std::process::exit(1);
+            }
+            app_exit.send(AppExit);
+        }
+    } else if scene_setup.is_done() {
+        *taken = true;
+        take_screenshot.send(TakeScreenshot {
+            path,
+            size: viewer_args.screenshot_size,
+            transparent: viewer_args.transparent,
+        });
+    }
+}
+
+/// Spawns a camera that renders the active camera's view into an image of the requested size.
+#[allow(clippy::type_complexity)]
+pub fn spawn_screenshot_cameras(
+    mut commands: Commands,
+    mut events: EventReader<TakeScreenshot>,
+    cameras: Query<
+        (
+            &Camera,
+            &Camera3d,
+            &GlobalTransform,
+            &Projection,
+            Option<&Tonemapping>,
+            Option<&RenderLayers>,
+        ),
+        Without<ScreenshotCamera>,
+    >,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    render_device: Option<Res<RenderDevice>>,
+    mut images: ResMut<Assets<Image>>,
+    mut screenshot_saved: EventWriter<ScreenshotSaved>,
+) {
+    for request in events.iter() {
+        // the same camera `active_camera` picks
+        let Some((camera, camera_3d, transform, projection, tonemapping, render_layers)) =
+            cameras.iter().find(|(camera, ..)| {
+                camera.is_active
+                    && camera.viewport.is_none()
+                    && matches!(camera.target, RenderTarget::Window(_))
+            })
+        else {
+            let error = "no camera to take it with".to_string();
+            error!("Could not take a screenshot: {}", error);
+            screenshot_saved.send(ScreenshotSaved {
+                path: request.path.clone(),
+                error: Some(error),
+            });
+            continue;
+        };
+        let size = request.size.unwrap_or_else(|| {
+            primary_window
+                .get_single()
+                .map_or(UVec2::new(1280, 720), |window| {
+                    UVec2::new(window.physical_width(), window.physical_height())
+                })
+        });
+        let max_size = render_device.as_ref().map_or(u32::MAX, |render_device| {
+            render_device.limits().max_texture_dimension_2d
+        });
+        if size.max_element() > max_size {
+            let error = format!(
+                "{}x{} is larger than the {}x{} the GPU allows",
+                size.x, size.y, max_size, max_size
+            );
+            error!("Could not take a screenshot: {}", error);
+            screenshot_saved.send(ScreenshotSaved {
+                path: request.path.clone(),
+                error: Some(error),
+            });
+            continue;
+        }
+
+        let extent = Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        };
+        let mut image = Image {
+            texture_descriptor: TextureDescriptor {
+                label: Some("screenshot"),
+                size: extent,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8UnormSrgb,
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::COPY_SRC
+                    | TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            },
+            ..default()
+        };
+        image.resize(extent);
+        let image = images.add(image);
+
+        let clear_color = if request.transparent {
+            ClearColorConfig::Custom(Color::NONE)
+        } else {
+            camera_3d.clear_color.clone()
+        };
+        let mut screenshot_camera = commands.spawn((
+            Camera3dBundle {
+                camera: Camera {
+                    target: RenderTarget::Image(image.clone()),
+                    hdr: camera.hdr,
+                    order: -1,
+                    ..default()
+                },
+                camera_3d: Camera3d {
+                    clear_color,
+                    ..camera_3d.clone()
+                },
+                projection: projection.clone(),
+                transform: transform.compute_transform(),
+                tonemapping: tonemapping.cloned().unwrap_or_default(),
+                ..default()
+            },
+            ScreenshotCamera {
+                request: request.clone(),
+                image,
+                size,
+                frames_left: WARMUP_FRAMES,
+                slot: ScreenshotSlot::default(),
+            },
+        ));
+        if let Some(render_layers) = render_layers {
+            screenshot_camera.insert(*render_layers);
+        }
+    }
+}
+
+/// Counts down the warmup frames and writes the captured pixels to disk.
+pub fn save_screenshots(
+    mut commands: Commands,
+    mut cameras: Query<(Entity, &mut ScreenshotCamera)>,
+    mut images: ResMut<Assets<Image>>,
+    mut screenshot_saved: EventWriter<ScreenshotSaved>,
+) {
+    for (entity, mut screenshot_camera) in &mut cameras {
+        if screenshot_camera.frames_left > 0 {
+            screenshot_camera.frames_left -= 1;
+            continue;
+        }
+        let Some(pixels) = screenshot_camera.slot.0.lock().unwrap().take() else {
+            continue;
+        };
+        commands.entity(entity).despawn_recursive();
+        images.remove(&screenshot_camera.image);
+
+        let request = &screenshot_camera.request;
+        let size = screenshot_camera.size;
+        let saved = pixels.and_then(|pixels| {
+            let image = Image::new(
+                Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                pixels,
+                TextureFormat::Rgba8UnormSrgb,
+            );
+            image
+                .try_into_dynamic()
+                .map_err(|error| error.to_string())
+                .and_then(|image| image.save(&request.path).map_err(|error| error.to_string()))
+        });
+        match &saved {
+            Ok(()) => info!(
+                "Saved a {}x{} screenshot to {}",
+                size.x,
+                size.y,
+                request.path.display()
+            ),
+            Err(error) => error!(
+                "Could not save the screenshot to {}: {}",
+                request.path.display(),
+                error
+            ),
+        }
+        screenshot_saved.send(ScreenshotSaved {
+            path: request.path.clone(),
+            error: saved.err(),
+        });
+    }
+}
+
+/// `screenshot-20230412-153012.png`, the time in UTC.
+pub fn timestamped_file_name(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, seconds_of_day) = (seconds / 86400, seconds % 86400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "screenshot-{:04}{:02}{:02}-{:02}{:02}{:02}.png",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Year, month and day of the day `days` after 1970-01-01, Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// A capture due this frame, in the render world.
+struct ScreenshotCopy {
+    image: Handle<Image>,
+    size: UVec2,
+    slot: ScreenshotSlot,
+    buffer: Option<Buffer>,
+}
+
+impl ScreenshotCopy {
+    /// Rows in a buffer a texture is copied to have to be aligned.
+    fn padded_bytes_per_row(&self) -> u32 {
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        (self.size.x * 4).div_ceil(align) * align
+    }
+}
+
+#[derive(Resource, Default)]
+struct ScreenshotCopies(Vec<ScreenshotCopy>);
+
+fn extract_screenshots(mut commands: Commands, cameras: Extract<Query<&ScreenshotCamera>>) {
+    let copies = cameras
+        .iter()
+        .filter(|camera| camera.frames_left == 0 && camera.slot.is_empty())
+        .map(|camera| ScreenshotCopy {
+            image: camera.image.clone_weak(),
+            size: camera.size,
+            slot: camera.slot.clone(),
+            buffer: None,
+        })
+        .collect();
+    commands.insert_resource(ScreenshotCopies(copies));
+}
+
+fn prepare_screenshot_buffers(
+    mut copies: ResMut<ScreenshotCopies>,
+    render_device: Res<RenderDevice>,
+) {
+    for copy in &mut copies.0 {
+        copy.buffer = Some(render_device.create_buffer(&BufferDescriptor {
+            label: Some("screenshot"),
+            size: (copy.padded_bytes_per_row() * copy.size.y) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        }));
+    }
+}
+
+/// Copies the screenshot images into their buffers after the cameras have rendered.
+struct ScreenshotNode;
+
+impl Node for ScreenshotNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let gpu_images = world.resource::<RenderAssets<Image>>();
+        for copy in &world.resource::<ScreenshotCopies>().0 {
+            let (Some(gpu_image), Some(buffer)) = (gpu_images.get(&copy.image), &copy.buffer)
+            else {
+                continue;
+            };
+            render_context.command_encoder().copy_texture_to_buffer(
+                gpu_image.texture.as_image_copy(),
+                ImageCopyBuffer {
+                    buffer,
+                    layout: ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: NonZeroU32::new(copy.padded_bytes_per_row()),
+                        rows_per_image: None,
+                    },
+                },
+                Extent3d {
+                    width: copy.size.x,
+                    height: copy.size.y,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Waits for the copies to finish and hands the pixels, without the row padding, to the main world.
+/// A buffer that can't be mapped fills the slot with the error, so the capture still ends.
+fn read_screenshot_buffers(copies: Res<ScreenshotCopies>, render_device: Res<RenderDevice>) {
+    for copy in &copies.0 {
+        let Some(buffer) = &copy.buffer else {
+            continue;
+        };
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        render_device.map_buffer(&slice, MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        render_device.poll(wgpu::Maintain::Wait);
+        let pixels = match receiver.try_recv() {
+            Ok(Ok(())) => {
+                let row_bytes = copy.size.x as usize * 4;
+                let pixels = slice
+                    .get_mapped_range()
+                    .chunks(copy.padded_bytes_per_row() as usize)
+                    .flat_map(|row| &row[..row_bytes])
+                    .copied()
+                    .collect();
+                buffer.unmap();
+                Ok(pixels)
+            }
+            Ok(Err(error)) => Err(format!("could not read it back from the GPU: {}", error)),
+            Err(_) => Err("could not read it back from the GPU".to_string()),
+        };
+
+        let mut slot = copy.slot.0.lock().unwrap();
+        if slot.is_none() {
+            *slot = Some(pixels);
+        }
+    }
+}
@@ -0,0 +1,753 @@
+//! Procedural meshes to go with `Cylinder`. Each shape is built around the Y axis and, like the
+//! cylinder, has normals and UVs. Wrap a shape in `WithTangents` for normal mapping.
+//!
+//! Most shapes are surfaces of revolution: an outline in the radius/height plane swept around the
+//! Y axis by `MeshBuilder::lathe`, with flat caps from `MeshBuilder::cap`. Triangles wind
+//! counter-clockwise seen from outside.
+
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use bevy::{
+    math::Vec2,
+    prelude::Mesh,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+
+/// A cone centered on the origin, with its base below and its tip up the Y axis.
+pub struct Cone {
+    /// Radius of the base.
+    pub radius: f32,
+    /// Height from the base to the tip.
+    pub height: f32,
+    /// Number of vertices around the base.
+    pub resolution: u32,
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            height: 1.0,
+            resolution: 16,
+        }
+    }
+}
+
+impl From<Cone> for Mesh {
+    fn from(c: Cone) -> Self {
+        debug_assert!(c.radius > 0.0);
+
+        Mesh::from(Frustum {
+            bottom_radius: c.radius,
+            top_radius: 0.0,
+            height: c.height,
+            resolution: c.resolution,
+        })
+    }
+}
+
+/// A cone cut off below the tip, centered on the origin like `Cylinder`.
+pub struct Frustum {
+    pub bottom_radius: f32,
+    /// Radius of the flat top, zero gives a cone without a top cap.
+    pub top_radius: f32,
+    pub height: f32,
+    /// Number of vertices around each ring.
+    pub resolution: u32,
+}
+
+impl Default for Frustum {
+    fn default() -> Self {
+        Self {
+            bottom_radius: 0.5,
+            top_radius: 0.25,
+            height: 1.0,
+            resolution: 16,
+        }
+    }
+}
+
+impl From<Frustum> for Mesh {
+    fn from(f: Frustum) -> Self {
+        debug_assert!(f.bottom_radius >= 0.0 && f.top_radius >= 0.0);
+        debug_assert!(f.bottom_radius + f.top_radius > 0.0);
+        debug_assert!(f.height > 0.0);
+        debug_assert!(f.resolution > 2);
+
+        let (bottom, top) = (-f.height / 2.0, f.height / 2.0);
+        let normal = Vec2::new(f.height, f.bottom_radius - f.top_radius);
+
+        let mut builder = MeshBuilder::default();
+        builder.lathe(
+            &[
+                ProfilePoint::new(f.bottom_radius, bottom, normal),
+                ProfilePoint::new(f.top_radius, top, normal),
+            ],
+            f.resolution,
+        );
+        if f.bottom_radius > 0.0 {
+            builder.cap(bottom, 0.0, f.bottom_radius, false, f.resolution);
+        }
+        if f.top_radius > 0.0 {
+            builder.cap(top, 0.0, f.top_radius, true, f.resolution);
+        }
+        builder.build()
+    }
+}
+
+/// A flat circle on the XZ plane, facing up.
+pub struct Disc {
+    pub radius: f32,
+    /// Number of vertices around the edge.
+    pub resolution: u32,
+}
+
+impl Default for Disc {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            resolution: 16,
+        }
+    }
+}
+
+impl From<Disc> for Mesh {
+    fn from(d: Disc) -> Self {
+        debug_assert!(d.radius > 0.0);
+        debug_assert!(d.resolution > 2);
+
+        let mut builder = MeshBuilder::default();
+        builder.cap(0.0, 0.0, d.radius, true, d.resolution);
+        builder.build()
+    }
+}
+
+/// Half a sphere with its flat side on the XZ plane and the dome up the Y axis.
+pub struct Hemisphere {
+    pub radius: f32,
+    /// Number of vertices around each ring.
+    pub resolution: u32,
+    /// Number of rings from the rim to the top.
+    pub rings: u32,
+}
+
+impl Default for Hemisphere {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            resolution: 16,
+            rings: 8,
+        }
+    }
+}
+
+impl From<Hemisphere> for Mesh {
+    fn from(h: Hemisphere) -> Self {
+        debug_assert!(h.radius > 0.0);
+        debug_assert!(h.resolution > 2);
+        debug_assert!(h.rings > 0);
+
+        let mut builder = MeshBuilder::default();
+        let dome: Vec<_> = arc(0.0, 0.0, h.radius, 0.0, FRAC_PI_2, h.rings).collect();
+        builder.lathe(&dome, h.resolution);
+        builder.cap(0.0, 0.0, h.radius, false, h.resolution);
+        builder.build()
+    }
+}
+
+/// A cylinder with rounded ends, centered on the origin along the Y axis.
+pub struct Capsule {
+    pub radius: f32,
+    /// Length of the straight part between the two rounded ends.
+    pub depth: f32,
+    /// Number of vertices around each ring.
+    pub resolution: u32,
+    /// Number of rings in each rounded end.
+    pub rings: u32,
+}
+
+impl Default for Capsule {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            depth: 1.0,
+            resolution: 16,
+            rings: 8,
+        }
+    }
+}
+
+impl From<Capsule> for Mesh {
+    fn from(c: Capsule) -> Self {
+        debug_assert!(c.radius > 0.0);
+        debug_assert!(c.depth >= 0.0);
+        debug_assert!(c.resolution > 2);
+        debug_assert!(c.rings > 0);
+
+        // the last ring of the bottom end and the first of the top end make the straight part
+        let half_depth = c.depth / 2.0;
+        let profile: Vec<_> = arc(0.0, -half_depth, c.radius, -FRAC_PI_2, 0.0, c.rings)
+            .chain(arc(0.0, half_depth, c.radius, 0.0, FRAC_PI_2, c.rings))
+            .collect();
+
+        let mut builder = MeshBuilder::default();
+        builder.lathe(&profile, c.resolution);
+        builder.build()
+    }
+}
+
+/// A ring shaped donut lying on the XZ plane.
+pub struct Torus {
+    /// Distance from the center to the middle of the tube.
+    pub radius: f32,
+    /// Radius of the tube itself.
+    pub tube_radius: f32,
+    /// Number of vertices around the Y axis.
+    pub resolution: u32,
+    /// Number of vertices around the tube.
+    pub tube_resolution: u32,
+}
+
+impl Default for Torus {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            tube_radius: 0.15,
+            resolution: 24,
+            tube_resolution: 12,
+        }
+    }
+}
+
+impl From<Torus> for Mesh {
+    fn from(t: Torus) -> Self {
+        debug_assert!(t.tube_radius > 0.0);
+        debug_assert!(t.radius > t.tube_radius);
+        debug_assert!(t.resolution > 2);
+        debug_assert!(t.tube_resolution > 2);
+
+        // starting and ending on the inside gives the UV seam its own vertices
+        let profile: Vec<_> =
+            arc(t.radius, 0.0, t.tube_radius, -PI, PI, t.tube_resolution).collect();
+
+        let mut builder = MeshBuilder::default();
+        builder.lathe(&profile, t.resolution);
+        builder.build()
+    }
+}
+
+/// A pipe centered on the origin like `Cylinder`, with a hole of `inner_radius` through it.
+pub struct Tube {
+    pub outer_radius: f32,
+    pub inner_radius: f32,
+    pub height: f32,
+    /// Number of vertices around each ring.
+    pub resolution: u32,
+}
+
+impl Default for Tube {
+    fn default() -> Self {
+        Self {
+            outer_radius: 0.5,
+            inner_radius: 0.4,
+            height: 1.0,
+            resolution: 16,
+        }
+    }
+}
+
+impl From<Tube> for Mesh {
+    fn from(t: Tube) -> Self {
+        debug_assert!(t.inner_radius > 0.0);
+        debug_assert!(t.outer_radius > t.inner_radius);
+        debug_assert!(t.height > 0.0);
+        debug_assert!(t.resolution > 2);
+
+        let (bottom, top) = (-t.height / 2.0, t.height / 2.0);
+        let (outward, inward) = (Vec2::X, Vec2::NEG_X);
+
+        let mut builder = MeshBuilder::default();
+        builder.lathe(
+            &[
+                ProfilePoint::new(t.outer_radius, bottom, outward),
+                ProfilePoint::new(t.outer_radius, top, outward),
+            ],
+            t.resolution,
+        );
+        builder.cap(top, t.inner_radius, t.outer_radius, true, t.resolution);
+        // going down the inside turns the faces in
+        builder.lathe(
+            &[
+                ProfilePoint::new(t.inner_radius, top, inward),
+                ProfilePoint::new(t.inner_radius, bottom, inward),
+            ],
+            t.resolution,
+        );
+        builder.cap(bottom, t.inner_radius, t.outer_radius, false, t.resolution);
+        builder.build()
+    }
+}
+
+/// An arrow from the origin up the Y axis: a thin shaft with a cone for the head.
+pub struct Arrow {
+    /// Total length, from the foot of the shaft to the tip of the head.
+    pub length: f32,
+    pub shaft_radius: f32,
+    pub head_radius: f32,
+    pub head_length: f32,
+    /// Number of vertices around each ring.
+    pub resolution: u32,
+}
+
+impl Default for Arrow {
+    fn default() -> Self {
+        Self {
+            length: 1.0,
+            shaft_radius: 0.02,
+            head_radius: 0.06,
+            head_length: 0.2,
+            resolution: 12,
+        }
+    }
+}
+
+impl From<Arrow> for Mesh {
+    fn from(a: Arrow) -> Self {
+        debug_assert!(a.shaft_radius > 0.0);
+        debug_assert!(a.head_radius > a.shaft_radius);
+        debug_assert!(a.head_length > 0.0 && a.head_length < a.length);
+        debug_assert!(a.resolution > 2);
+
+        let neck = a.length - a.head_length;
+        let head_normal = Vec2::new(a.head_length, a.head_radius);
+
+        let mut builder = MeshBuilder::default();
+        builder.cap(0.0, 0.0, a.shaft_radius, false, a.resolution);
+        builder.lathe(
+            &[
+                ProfilePoint::new(a.shaft_radius, 0.0, Vec2::X),
+                ProfilePoint::new(a.shaft_radius, neck, Vec2::X),
+            ],
+            a.resolution,
+        );
+        // the underside of the head, around the shaft
+        builder.cap(neck, a.shaft_radius, a.head_radius, false, a.resolution);
+        builder.lathe(
+            &[
+                ProfilePoint::new(a.head_radius, neck, head_normal),
+                ProfilePoint::new(0.0, a.length, head_normal),
+            ],
+            a.resolution,
+        );
+        builder.build()
+    }
+}
+
+/// Any of the shapes with tangents added, for materials with a normal map.
+pub struct WithTangents<T>(pub T);
+
+impl<T> From<WithTangents<T>> for Mesh
+where
+    Mesh: From<T>,
+{
+    fn from(WithTangents(shape): WithTangents<T>) -> Self {
+        let mut mesh = Mesh::from(shape);
+        // the shapes always have the positions, normals, UVs and indices this needs
+        mesh.generate_tangents()
+            .expect("shape meshes have everything needed for tangents");
+        mesh
+    }
+}
+
+/// A point on the outline swept around the Y axis by `MeshBuilder::lathe`.
+#[derive(Debug, Clone, Copy)]
+struct ProfilePoint {
+    radius: f32,
+    y: f32,
+    /// Normal in the radius/height plane, x pointing away from the axis.
+    normal: Vec2,
+}
+
+impl ProfilePoint {
+    fn new(radius: f32, y: f32, normal: Vec2) -> Self {
+        ProfilePoint {
+            radius,
+            y,
+            normal: normal.normalize(),
+        }
+    }
+}
+
+/// `steps + 1` points on a circular arc in the radius/height plane around
+/// (`center_radius`, `center_y`), from angle `from` to `to` measured from the outward direction
+/// towards +Y, with normals pointing away from the arc's center.
+fn arc(
+    center_radius: f32,
+    center_y: f32,
+    radius: f32,
+    from: f32,
+    to: f32,
+    steps: u32,
+) -> impl Iterator<Item = ProfilePoint> {
+    (0..=steps).map(move |step| {
+        let angle = from + (to - from) * step as f32 / steps as f32;
+        let (sin, cos) = angle.sin_cos();
+        ProfilePoint {
+            radius: (center_radius + radius * cos).max(0.0),
+            y: center_y + radius * sin,
+            normal: Vec2::new(cos, sin),
+        }
+    })
+}
+
+/// Vertex and index data of a shape being built.
+#[derive(Default)]
+struct MeshBuilder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    /// Sweeps `profile` around the Y axis, one ring of `resolution + 1` vertices per point (the
+    /// first vertex is repeated for the UV seam). A point on the axis is a pole instead, with one
+    /// vertex per face in the middle of it, closed by a fan. The faces point to the right of the
+    /// direction the profile is walked in, so walking up the outside faces out. U goes around the
+    /// axis, V along the profile by distance.
+    fn lathe(&mut self, profile: &[ProfilePoint], resolution: u32) {
+        let mut distances = vec![0.0];
+        for pair in profile.windows(2) {
+            let step = Vec2::new(pair[1].radius - pair[0].radius, pair[1].y - pair[0].y);
+            distances.push(distances.last().unwrap() + step.length());
+        }
+        let total_distance = distances.last().unwrap().max(f32::EPSILON);
+
+        let mut rings = Vec::with_capacity(profile.len());
+        for (point, distance) in profile.iter().zip(distances) {
+            rings.push(self.positions.len() as u32);
+            let (count, shift) = if point.radius > 0.0 {
+                (resolution + 1, 0.0)
+            } else {
+                (resolution, 0.5)
+            };
+
+            for segment in 0..count {
+                let u = (segment as f32 + shift) / resolution as f32;
+                let (sin, cos) = (u * TAU).sin_cos();
+
+                self.positions
+                    .push([point.radius * cos, point.y, point.radius * sin]);
+                self.normals
+                    .push([point.normal.x * cos, point.normal.y, point.normal.x * sin]);
+                self.uvs.push([u, distance / total_distance]);
+            }
+        }
+
+        for (i, pair) in profile.windows(2).enumerate() {
+            let (ring, next_ring) = (rings[i], rings[i + 1]);
+            let (ring_is_pole, next_is_pole) = (pair[0].radius <= 0.0, pair[1].radius <= 0.0);
+
+            for j in 0..resolution {
+                if !ring_is_pole {
+                    self.indices
+                        .extend_from_slice(&[ring + j, next_ring + j, ring + j + 1]);
+                }
+                if !next_is_pole {
+                    let corner = if ring_is_pole { ring + j } else { ring + j + 1 };
+                    self.indices
+                        .extend_from_slice(&[next_ring + j, next_ring + j + 1, corner]);
+                }
+            }
+        }
+    }
+
+    /// A flat ring between `inner` and `outer` radius at height `y`, facing up or down. An inner
+    /// radius of zero makes a disc. UVs are a top down projection, like the cylinder's caps.
+    fn cap(&mut self, y: f32, inner: f32, outer: f32, up: bool, resolution: u32) {
+        let offset = self.positions.len() as u32;
+        let step_theta = TAU / resolution as f32;
+        let normal = [0.0, if up { 1.0 } else { -1.0 }, 0.0];
+        let mut push = |radius: f32, theta: f32| {
+            let (sin, cos) = theta.sin_cos();
+            let (x, z) = (radius * cos, radius * sin);
+            self.positions.push([x, y, z]);
+            self.normals.push(normal);
+            self.uvs
+                .push([0.5 + 0.5 * x / outer, 0.5 - 0.5 * z / outer]);
+        };
+
+        if inner <= 0.0 {
+            // a fan around the center
+            push(0.0, 0.0);
+            for i in 0..resolution {
+                push(outer, i as f32 * step_theta);
+            }
+            for i in 0..resolution {
+                let (a, b) = (offset + 1 + i, offset + 1 + (i + 1) % resolution);
+                if up {
+                    self.indices.extend_from_slice(&[offset, b, a]);
+                } else {
+                    self.indices.extend_from_slice(&[offset, a, b]);
+                }
+            }
+        } else {
+            // inner and outer vertex pairs
+            for i in 0..resolution {
+                let theta = i as f32 * step_theta;
+                push(inner, theta);
+                push(outer, theta);
+            }
+            for i in 0..resolution {
+                let (inner_a, outer_a) = (offset + 2 * i, offset + 2 * i + 1);
+                let next = (i + 1) % resolution;
+                let (inner_b, outer_b) = (offset + 2 * next, offset + 2 * next + 1);
+                if up {
+                    self.indices
+                        .extend_from_slice(&[outer_a, inner_a, inner_b, outer_a, inner_b, outer_b]);
+                } else {
+                    self.indices
+                        .extend_from_slice(&[outer_a, inner_b, inner_a, outer_a, outer_b, inner_b]);
+                }
+            }
+        }
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use bevy::{
+        math::Vec3,
+        render::mesh::{MeshVertexAttribute, VertexAttributeValues},
+    };
+
+    use super::*;
+
+    fn attribute<const N: usize>(mesh: &Mesh, attribute: MeshVertexAttribute) -> Vec<[f32; N]> {
+        let values = match mesh.attribute(attribute.clone()) {
+            Some(VertexAttributeValues::Float32x2(values)) => {
+                values.iter().map(|v| v[..N].try_into().unwrap()).collect()
+            }
+            Some(VertexAttributeValues::Float32x3(values)) => {
+                values.iter().map(|v| v[..N].try_into().unwrap()).collect()
+            }
+            _ => panic!("no {} attribute", attribute.name),
+        };
+        values
+    }
+
+    /// Checks what every shape needs, and returns its positions and triangles: indices in range,
+    /// a normal and UV per vertex, unit normals, and triangles with some area that wind
+    /// counter-clockwise seen from the side their vertex normals face.
+    pub(crate) fn check_shape(mesh: &Mesh) -> (Vec<Vec3>, Vec<[usize; 3]>) {
+        assert_eq!(mesh.primitive_topology(), PrimitiveTopology::TriangleList);
+        let positions: Vec<Vec3> = attribute::<3>(mesh, Mesh::ATTRIBUTE_POSITION)
+            .into_iter()
+            .map(Vec3::from)
+            .collect();
+        let normals: Vec<Vec3> = attribute::<3>(mesh, Mesh::ATTRIBUTE_NORMAL)
+            .into_iter()
+            .map(Vec3::from)
+            .collect();
+        let uvs = attribute::<2>(mesh, Mesh::ATTRIBUTE_UV_0);
+        assert_eq!(normals.len(), positions.len());
+        assert_eq!(uvs.len(), positions.len());
+
+        for normal in &normals {
+            assert!(
+                (normal.length() - 1.0).abs() < 1e-5,
+                "{:?} is not a unit normal",
+                normal
+            );
+        }
+
+        let indices: Vec<usize> = mesh.indices().expect("shapes are indexed").iter().collect();
+        assert_eq!(indices.len() % 3, 0);
+        for &index in &indices {
+            assert!(index < positions.len(), "index {} is out of range", index);
+        }
+
+        let triangles: Vec<[usize; 3]> = indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect();
+        for &[a, b, c] in &triangles {
+            let face = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+            assert!(face.length() > 1e-8, "triangle {:?} has no area", [a, b, c]);
+            let normal = normals[a] + normals[b] + normals[c];
+            assert!(
+                face.dot(normal) > 0.0,
+                "triangle {:?} winds clockwise",
+                [a, b, c]
+            );
+        }
+        (positions, triangles)
+    }
+
+    fn extents(positions: &[Vec3]) -> (Vec3, Vec3) {
+        let min = positions
+            .iter()
+            .fold(Vec3::splat(f32::MAX), |min, p| min.min(*p));
+        let max = positions
+            .iter()
+            .fold(Vec3::splat(f32::MIN), |max, p| max.max(*p));
+        (min, max)
+    }
+
+    fn horizontal_distances(positions: &[Vec3]) -> (f32, f32) {
+        positions
+            .iter()
+            .map(|p| Vec2::new(p.x, p.z).length())
+            .fold((f32::MAX, f32::MIN), |(min, max), d| {
+                (min.min(d), max.max(d))
+            })
+    }
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} is not {}", a, b);
+    }
+
+    #[test]
+    fn cone() {
+        let (positions, triangles) = check_shape(&Mesh::from(Cone {
+            radius: 0.5,
+            height: 2.0,
+            resolution: 8,
+        }));
+        // a base ring and a tip per face for the side, and a fan for the base
+        assert_eq!(positions.len(), 9 + 8 + 9);
+        assert_eq!(triangles.len(), 8 + 8);
+        let (min, max) = extents(&positions);
+        assert_near(min.y, -1.0);
+        assert_near(max.y, 1.0);
+        assert_near(horizontal_distances(&positions).1, 0.5);
+    }
+
+    #[test]
+    fn frustum() {
+        let (positions, triangles) = check_shape(&Mesh::from(Frustum {
+            bottom_radius: 0.5,
+            top_radius: 0.25,
+            height: 1.0,
+            resolution: 8,
+        }));
+        assert_eq!(positions.len(), 2 * 9 + 2 * 9);
+        assert_eq!(triangles.len(), 8 * 2 + 2 * 8);
+        let (min, max) = extents(&positions);
+        assert_near(min.y, -0.5);
+        assert_near(max.y, 0.5);
+        assert_near(horizontal_distances(&positions).1, 0.5);
+    }
+
+    #[test]
+    fn disc() {
+        let mesh = Mesh::from(Disc {
+            radius: 0.5,
+            resolution: 8,
+        });
+        let (positions, triangles) = check_shape(&mesh);
+        assert_eq!(positions.len(), 1 + 8);
+        assert_eq!(triangles.len(), 8);
+        assert!(positions.iter().all(|p| p.y == 0.0));
+        let normals = attribute::<3>(&mesh, Mesh::ATTRIBUTE_NORMAL);
+        assert!(normals.iter().all(|n| *n == [0.0, 1.0, 0.0]));
+    }
+
+    #[test]
+    fn hemisphere() {
+        let (positions, triangles) = check_shape(&Mesh::from(Hemisphere {
+            radius: 0.5,
+            resolution: 8,
+            rings: 4,
+        }));
+        // four rings up from the rim, a pole with a vertex per face, and the flat side
+        assert_eq!(positions.len(), 4 * 9 + 8 + 9);
+        assert_eq!(triangles.len(), 3 * 8 * 2 + 8 + 8);
+        let (min, max) = extents(&positions);
+        assert_near(min.y, 0.0);
+        assert_near(max.y, 0.5);
+        for p in &positions {
+            assert!(p.length() < 0.5 + 1e-5);
+        }
+    }
+
+    #[test]
+    fn capsule() {
+        let (positions, triangles) = check_shape(&Mesh::from(Capsule {
+            radius: 0.5,
+            depth: 1.0,
+            resolution: 8,
+            rings: 4,
+        }));
+        // a pole at each end and the other eight rings
+        assert_eq!(positions.len(), 2 * 8 + 8 * 9);
+        assert_eq!(triangles.len(), 2 * 8 + 7 * 8 * 2);
+        let (min, max) = extents(&positions);
+        assert_near(min.y, -1.0);
+        assert_near(max.y, 1.0);
+        assert_near(horizontal_distances(&positions).1, 0.5);
+    }
+
+    #[test]
+    fn torus() {
+        let (positions, triangles) = check_shape(&Mesh::from(Torus {
+            radius: 1.0,
+            tube_radius: 0.25,
+            resolution: 8,
+            tube_resolution: 6,
+        }));
+        assert_eq!(positions.len(), 7 * 9);
+        assert_eq!(triangles.len(), 6 * 8 * 2);
+        let (inner, outer) = horizontal_distances(&positions);
+        assert_near(inner, 0.75);
+        assert_near(outer, 1.25);
+        let (min, max) = extents(&positions);
+        assert!(min.y > -0.25 - 1e-5 && max.y < 0.25 + 1e-5);
+    }
+
+    #[test]
+    fn tube() {
+        let (positions, triangles) = check_shape(&Mesh::from(Tube {
+            outer_radius: 0.5,
+            inner_radius: 0.25,
+            height: 1.0,
+            resolution: 8,
+        }));
+        // the outside and inside walls, and a ring of vertex pairs for each end
+        assert_eq!(positions.len(), 2 * (2 * 9) + 2 * (2 * 8));
+        assert_eq!(triangles.len(), 4 * 8 * 2);
+        let (inner, outer) = horizontal_distances(&positions);
+        assert_near(inner, 0.25);
+        assert_near(outer, 0.5);
+        let (min, max) = extents(&positions);
+        assert_near(min.y, -0.5);
+        assert_near(max.y, 0.5);
+    }
+
+    #[test]
+    fn arrow() {
+        let (positions, triangles) = check_shape(&Mesh::from(Arrow {
+            length: 1.0,
+            shaft_radius: 0.02,
+            head_radius: 0.06,
+            head_length: 0.2,
+            resolution: 8,
+        }));
+        // the foot, the shaft, the underside of the head and the head up to its tip
+        assert_eq!(positions.len(), 9 + 2 * 9 + 2 * 8 + (9 + 8));
+        assert_eq!(triangles.len(), 8 + 8 * 2 + 8 * 2 + 8);
+        let (min, max) = extents(&positions);
+        assert_near(min.y, 0.0);
+        assert_near(max.y, 1.0);
+        assert_near(horizontal_distances(&positions).1, 0.06);
+    }
+}
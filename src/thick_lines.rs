@@ -0,0 +1,327 @@
+//! Lines wider than a pixel, drawn as quads that are turned towards the camera in the vertex
+//! shader. Unlike `PolygonMode::Line` this works on every backend.
+//!
+//! Any `LineList` can be drawn thick by spawning `Mesh::from(ThickLines(line_list))` with a
+//! `ThickLineMaterial`; a `LineStrip` or `LineLoop` converts with `LineList::from` first.
+
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        mesh::{Indices, MeshVertexAttribute, MeshVertexBufferLayout, PrimitiveTopology},
+        render_asset::RenderAssets,
+        render_resource::{
+            AsBindGroup, AsBindGroupShaderType, CompareFunction, RenderPipelineDescriptor,
+            ShaderRef, ShaderType, SpecializedMeshPipelineError, VertexFormat,
+        },
+    },
+};
+
+use crate::lines::LineList;
+
+/// The other end of the line a vertex belongs to.
+pub const ATTRIBUTE_LINE_OTHER_END: MeshVertexAttribute =
+    MeshVertexAttribute::new("LineOtherEnd", 620_417_301, VertexFormat::Float32x3);
+/// Which side of the line a vertex is pushed to (-1 or 1), and how far along the line it is in
+/// world units, for dashing.
+pub const ATTRIBUTE_LINE_SIDE_DISTANCE: MeshVertexAttribute =
+    MeshVertexAttribute::new("LineSideDistance", 620_417_302, VertexFormat::Float32x2);
+
+/// How wide a thick line is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineWidth {
+    /// The same number of pixels however far away the line is.
+    Pixels(f32),
+    /// A width in the world, so the line gets thinner with distance.
+    World(f32),
+}
+
+impl Default for LineWidth {
+    fn default() -> Self {
+        LineWidth::Pixels(2.0)
+    }
+}
+
+/// Draws a `ThickLines` mesh. Vertex colors are multiplied by `color`, the same as `LineMaterial`.
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "4e5b4a8e-3c1d-4f65-9b7e-0d2f6a9c1e77"]
+#[bind_group_data(ThickLineMaterialKey)]
+#[uniform(0, ThickLineMaterialUniform)]
+pub struct ThickLineMaterial {
+    pub color: Color,
+    pub width: LineWidth,
+    /// Length of the dashes and of the gaps between them in world units, `None` for a solid line.
+    pub dashes: Option<(f32, f32)>,
+    /// Hide the parts of the line behind other geometry. Lines without depth test are drawn on
+    /// top of everything.
+    pub depth_test: bool,
+}
+
+impl Default for ThickLineMaterial {
+    fn default() -> Self {
+        ThickLineMaterial {
+            color: Color::WHITE,
+            width: LineWidth::default(),
+            dashes: None,
+            depth_test: true,
+        }
+    }
+}
+
+#[derive(Clone, Default, ShaderType)]
+pub struct ThickLineMaterialUniform {
+    pub color: Vec4,
+    pub width: f32,
+    /// 1 when `width` is in world units, 0 for pixels.
+    pub world_width: u32,
+    pub dash_length: f32,
+    pub gap_length: f32,
+}
+
+impl AsBindGroupShaderType<ThickLineMaterialUniform> for ThickLineMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<Image>) -> ThickLineMaterialUniform {
+        let (width, world_width) = match self.width {
+            LineWidth::Pixels(width) => (width, 0),
+            LineWidth::World(width) => (width, 1),
+        };
+        let (dash_length, gap_length) = self.dashes.unwrap_or((0.0, 0.0));
+        ThickLineMaterialUniform {
+            color: self.color.as_linear_rgba_f32().into(),
+            width,
+            world_width,
+            dash_length,
+            gap_length,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ThickLineMaterialKey {
+    depth_test: bool,
+}
+
+impl From<&ThickLineMaterial> for ThickLineMaterialKey {
+    fn from(material: &ThickLineMaterial) -> Self {
+        ThickLineMaterialKey {
+            depth_test: material.depth_test,
+        }
+    }
+}
+
+impl Material for ThickLineMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "assets/shaders/thick_line_material.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "assets/shaders/thick_line_material.wgsl".into()
+    }
+
+    // blended so lines without depth test are drawn after, and over, the opaque meshes
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let mut attributes = vec![
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            ATTRIBUTE_LINE_OTHER_END.at_shader_location(1),
+            ATTRIBUTE_LINE_SIDE_DISTANCE.at_shader_location(2),
+        ];
+        // the mesh pipeline has already set VERTEX_COLORS for the shader
+        if layout.contains(Mesh::ATTRIBUTE_COLOR) {
+            attributes.push(Mesh::ATTRIBUTE_COLOR.at_shader_location(3));
+        }
+        descriptor.vertex.buffers = vec![layout.get_layout(&attributes)?];
+        // which way round a quad ends up depends on the view
+        descriptor.primitive.cull_mode = None;
+
+        if !key.bind_group_data.depth_test {
+            if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+                depth_stencil.depth_compare = CompareFunction::Always;
+                depth_stencil.depth_write_enabled = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A `LineList` drawn with quads, see `ThickLineMaterial`.
+#[derive(Debug, Clone, Default)]
+pub struct ThickLines(pub LineList);
+
+impl From<ThickLines> for Mesh {
+    fn from(ThickLines(line_list): ThickLines) -> Self {
+        let line_count = line_list.lines.len();
+        let mut positions = Vec::with_capacity(line_count * 4);
+        let mut other_ends = Vec::with_capacity(line_count * 4);
+        let mut side_distances = Vec::with_capacity(line_count * 4);
+        let mut colors = Vec::with_capacity(line_list.colors.len() * 4);
+        let mut indices = Vec::with_capacity(line_count * 6);
+
+        // like `LineList`, mismatched colors are left out and the material color is used
+        let with_colors = !line_list.colors.is_empty() && line_list.colors.len() == line_count;
+        if !line_list.colors.is_empty() && !with_colors {
+            warn!(
+                "Thick lines need one color pair per line or none at all, got {} pairs for {} lines",
+                line_list.colors.len(),
+                line_count
+            );
+        }
+
+        let mut distance = 0.0;
+        let mut previous_end = None;
+        for (index, (start, end)) in line_list.lines.iter().copied().enumerate() {
+            // dashes carry on along lines that join up, like the ones from a strip
+            if previous_end != Some(start) {
+                distance = 0.0;
+            }
+            let end_distance = distance + start.distance(end);
+
+            // the end vertices see the line from the other side, so their sides are swapped
+            positions.extend([start, start, end, end]);
+            other_ends.extend([end, end, start, start]);
+            side_distances.extend([
+                [1.0, distance],
+                [-1.0, distance],
+                [-1.0, end_distance],
+                [1.0, end_distance],
+            ]);
+            if let Some((start_color, end_color)) =
+                line_list.colors.get(index).filter(|_| with_colors)
+            {
+                let (start_color, end_color) = (
+                    start_color.as_linear_rgba_f32(),
+                    end_color.as_linear_rgba_f32(),
+                );
+                colors.extend([start_color, start_color, end_color, end_color]);
+            }
+
+            let first = index as u32 * 4;
+            indices.extend([first, first + 1, first + 2, first + 2, first + 1, first + 3]);
+
+            distance = end_distance;
+            previous_end = Some(end);
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        if with_colors {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        }
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(ATTRIBUTE_LINE_OTHER_END, other_ends);
+        mesh.insert_attribute(ATTRIBUTE_LINE_SIDE_DISTANCE, side_distances);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::mesh::VertexAttributeValues;
+
+    use crate::lines::LineStrip;
+
+    use super::*;
+
+    fn float3(mesh: &Mesh, attribute: MeshVertexAttribute) -> Vec<Vec3> {
+        match mesh.attribute(attribute) {
+            Some(VertexAttributeValues::Float32x3(values)) => {
+                values.iter().copied().map(Vec3::from).collect()
+            }
+            _ => panic!("no float3 attribute"),
+        }
+    }
+
+    fn side_distances(mesh: &Mesh) -> Vec<[f32; 2]> {
+        match mesh.attribute(ATTRIBUTE_LINE_SIDE_DISTANCE) {
+            Some(VertexAttributeValues::Float32x2(values)) => values.clone(),
+            _ => panic!("no side and distance attribute"),
+        }
+    }
+
+    #[test]
+    fn a_quad_per_line() {
+        let (start, end) = (Vec3::ZERO, Vec3::new(2.0, 0.0, 0.0));
+        let mesh = Mesh::from(ThickLines(LineList {
+            lines: vec![(start, end), (Vec3::Y, Vec3::Z)],
+            ..default()
+        }));
+        assert_eq!(mesh.primitive_topology(), PrimitiveTopology::TriangleList);
+        assert_eq!(mesh.count_vertices(), 2 * 4);
+        let indices: Vec<usize> = mesh.indices().unwrap().iter().collect();
+        assert_eq!(indices.len(), 2 * 6);
+        assert_eq!(&indices[..6], &[0, 1, 2, 2, 1, 3]);
+        assert!(indices[6..].iter().all(|&index| (4..8).contains(&index)));
+
+        // two corners at each end, each knowing the other end and pushed to opposite sides
+        let positions = float3(&mesh, Mesh::ATTRIBUTE_POSITION);
+        let other_ends = float3(&mesh, ATTRIBUTE_LINE_OTHER_END);
+        assert_eq!(&positions[..4], &[start, start, end, end]);
+        assert_eq!(&other_ends[..4], &[end, end, start, start]);
+        let sides: Vec<f32> = side_distances(&mesh)
+            .iter()
+            .map(|[side, _]| *side)
+            .collect();
+        assert_eq!(&sides[..4], &[1.0, -1.0, -1.0, 1.0]);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_COLOR).is_none());
+    }
+
+    #[test]
+    fn dash_distance_carries_on_along_joined_lines() {
+        let mesh = Mesh::from(ThickLines(LineList::from(LineStrip {
+            points: vec![Vec3::ZERO, Vec3::X, Vec3::new(1.0, 2.0, 0.0)],
+            ..default()
+        })));
+        let distances: Vec<f32> = side_distances(&mesh)
+            .iter()
+            .map(|[_, distance]| *distance)
+            .collect();
+        assert_eq!(distances, vec![0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 3.0, 3.0]);
+
+        // a line that doesn't start where the last one ended starts its dashes over
+        let mesh = Mesh::from(ThickLines(LineList {
+            lines: vec![(Vec3::ZERO, Vec3::X), (Vec3::Y, Vec3::new(0.0, 3.0, 0.0))],
+            ..default()
+        }));
+        let distances: Vec<f32> = side_distances(&mesh)
+            .iter()
+            .map(|[_, distance]| *distance)
+            .collect();
+        assert_eq!(distances, vec![0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn colors_per_corner() {
+        let mesh = Mesh::from(ThickLines(LineList {
+            lines: vec![(Vec3::ZERO, Vec3::X)],
+            colors: vec![(Color::RED, Color::BLUE)],
+        }));
+        let (red, blue) = (
+            Color::RED.as_linear_rgba_f32(),
+            Color::BLUE.as_linear_rgba_f32(),
+        );
+        match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            Some(VertexAttributeValues::Float32x4(colors)) => {
+                assert_eq!(colors, &vec![red, red, blue, blue])
+            }
+            _ => panic!("no colors"),
+        }
+    }
+
+    #[test]
+    fn mismatched_colors_are_left_out() {
+        let mesh = Mesh::from(ThickLines(LineList {
+            lines: vec![(Vec3::ZERO, Vec3::X), (Vec3::X, Vec3::Y)],
+            colors: vec![(Color::RED, Color::BLUE)],
+        }));
+        assert_eq!(mesh.count_vertices(), 8);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_COLOR).is_none());
+    }
+}
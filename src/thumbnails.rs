@@ -0,0 +1,409 @@
+//! Batch mode that saves a square thumbnail of every model, one model at a time, and writes a
+//! contact sheet of them as `index.html` and `index.json`.
+//!
+//! Each model is loaded and framed by the viewer as usual, so the thumbnails show the three-quarter
+//! view `setup_scene_after_load` gives a freshly loaded model.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::Serialize;
+
+use crate::args::ModelPath;
+use crate::model_stats::ModelStats;
+use crate::report::rounded;
+use crate::scene_setup::{expand_wildcards, LoadModels, LoadStatus, SceneHandle, SceneSetup};
+use crate::screenshot::{ScreenshotSaved, TakeScreenshot};
+
+/// One entry of the contact sheet.
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub model: String,
+    /// File name of the image in the output folder, `None` when there is none.
+    pub image: Option<String>,
+    pub stats: Option<ModelStats>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BatchState {
+    /// Ready to load the next model.
+    Next,
+    /// Waiting for the model to be spawned and framed.
+    Loading,
+    /// Waiting for the screenshot to be written.
+    Capturing,
+}
+
+#[derive(Resource)]
+pub struct ThumbnailBatch {
+    /// Asset paths of the models, in order.
+    models: Vec<String>,
+    folder: PathBuf,
+    size: u32,
+    transparent: bool,
+    state: BatchState,
+    thumbnails: Vec<Thumbnail>,
+}
+
+impl ThumbnailBatch {
+    /// A batch over the models, with wildcards and folders expanded. Creates the output `folder`.
+    pub fn new(
+        models: &[ModelPath],
+        folder: &str,
+        size: u32,
+        transparent: bool,
+    ) -> std::io::Result<Self> {
+        std::fs::create_dir_all(folder)?;
+        Ok(ThumbnailBatch {
+            models: models
+                .iter()
+                .flat_map(|model| expand_wildcards(&model.path))
+                .collect(),
+            folder: PathBuf::from(folder),
+            size,
+            transparent,
+            state: BatchState::Next,
+            thumbnails: Vec::new(),
+        })
+    }
+
+    fn current(&self) -> Option<&str> {
+        self.models.get(self.thumbnails.len()).map(String::as_str)
+    }
+
+    fn write_index(&self) -> std::io::Result<()> {
+        std::fs::write(
+            self.folder.join("index.html"),
+            html_index(&self.thumbnails, self.size),
+        )?;
+        std::fs::write(
+            self.folder.join("index.json"),
+            json_index(&self.thumbnails, self.size),
+        )
+    }
+}
+
+/// The image file for a model, its file name with `.png` added so `a.glb` and `a.gltf` don't clash.
+fn image_name(model: &str) -> String {
+    let name = Path::new(model).file_name().map_or_else(
+        || model.to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    format!("{}.png", name)
+}
+
+/// Loads each model in turn, takes its thumbnail once it is framed, and writes the index and quits
+/// after the last one, with a non-zero exit status when any model failed.
+pub fn run_thumbnail_batch(
+    mut thumbnail_batch: ResMut<ThumbnailBatch>,
+    scene_setup: Res<SceneSetup>,
+    scene_handles: Query<(&SceneHandle, Option<&ModelStats>)>,
+    mut load_models: EventWriter<LoadModels>,
+    mut take_screenshot: EventWriter<TakeScreenshot>,
+    mut screenshot_saved: EventReader<ScreenshotSaved>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    let thumbnail_batch = &mut *thumbnail_batch;
+    let Some(model) = thumbnail_batch.current().map(str::to_string) else {
+        if let Err(error) = thumbnail_batch.write_index() {
+            error!("Could not write the thumbnail index: {}", error);
+        }
+        let failed = thumbnail_batch
+            .thumbnails
+            .iter()
+            .filter(|thumbnail| thumbnail.error.is_some())
+            .count();
+        info!(
+            "Saved {} thumbnails to {}, {} models failed",
+            thumbnail_batch.thumbnails.len() - failed,
+            thumbnail_batch.folder.display(),
+            failed
+        );
+        if failed > 0 {
+            std::process::exit(1);
+        }
+        app_exit.send(AppExit);
+        return;
+    };
+
+    // the previous model's handles are only gone once `load_models` has taken the new request
+    let loaded = || {
+        !scene_handles.is_empty()
+            && scene_handles
+                .iter()
+                .all(|(scene_handle, _)| scene_handle.path() == model)
+    };
+
+    match thumbnail_batch.state {
+        BatchState::Next => {
+            info!(
+                "Thumbnail {} of {}: {}",
+                thumbnail_batch.thumbnails.len() + 1,
+                thumbnail_batch.models.len(),
+                model
+            );
+            load_models.send(LoadModels(vec![ModelPath {
+                path: model,
+                scene: None,
+            }]));
+            thumbnail_batch.state = BatchState::Loading;
+        }
+        BatchState::Loading if loaded() => {
+            let Ok((scene_handle, _)) = scene_handles.get_single() else {
+                return;
+            };
+            if let LoadStatus::Failed { reason, .. } = scene_handle.status() {
+                thumbnail_batch.thumbnails.push(Thumbnail {
+                    model,
+                    image: None,
+                    stats: None,
+                    error: Some(reason.clone()),
+                });
+                thumbnail_batch.state = BatchState::Next;
+            } else if scene_setup.is_done() {
+                take_screenshot.send(TakeScreenshot {
+                    path: thumbnail_batch.folder.join(image_name(&model)),
+                    size: Some(UVec2::splat(thumbnail_batch.size)),
+                    transparent: thumbnail_batch.transparent,
+                });
+                thumbnail_batch.state = BatchState::Capturing;
+            }
+        }
+        BatchState::Loading => {}
+        BatchState::Capturing => {
+            let path = thumbnail_batch.folder.join(image_name(&model));
+            let Some(saved) = screenshot_saved.iter().find(|saved| saved.path == path) else {
+                return;
+            };
+            let stats = scene_handles
+                .get_single()
+                .ok()
+                .and_then(|(_, stats)| stats.cloned());
+            thumbnail_batch.thumbnails.push(Thumbnail {
+                image: saved.error.is_none().then(|| image_name(&model)),
+                model,
+                stats,
+                error: saved.error.clone(),
+            });
+            thumbnail_batch.state = BatchState::Next;
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonThumbnail<'a> {
+    model: &'a str,
+    image: Option<&'a str>,
+    triangles: Option<usize>,
+    size: Option<[Option<f64>; 3]>,
+    error: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonIndex<'a> {
+    schema_version: u32,
+    size: u32,
+    thumbnails: Vec<JsonThumbnail<'a>>,
+}
+
+/// `{"schema_version": 1, "size": 256, "thumbnails": [...]}`.
+pub fn json_index(thumbnails: &[Thumbnail], size: u32) -> String {
+    let index = JsonIndex {
+        schema_version: 1,
+        size,
+        thumbnails: thumbnails
+            .iter()
+            .map(|thumbnail| JsonThumbnail {
+                model: &thumbnail.model,
+                image: thumbnail.image.as_deref(),
+                triangles: thumbnail.stats.as_ref().map(|stats| stats.triangles),
+                size: thumbnail
+                    .stats
+                    .as_ref()
+                    .map(|stats| stats.size.to_array().map(rounded)),
+                error: thumbnail.error.as_deref(),
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&index).expect("the index only holds strings and numbers") + "\n"
+}
+
+/// A page with every thumbnail and its model's name, triangles and size under it.
+pub fn html_index(thumbnails: &[Thumbnail], size: u32) -> String {
+    let mut html = String::new();
+    writeln!(
+        html,
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Models</title>
+<style>
+body {{ font-family: sans-serif; background: #202020; color: #d0d0d0; }}
+.sheet {{ display: flex; flex-wrap: wrap; gap: 12px; }}
+figure {{ margin: 0; width: {size}px; }}
+figure img, figure .missing {{ width: {size}px; height: {size}px; background: #303030; }}
+figure .missing {{ display: flex; align-items: center; justify-content: center; color: #e07070; }}
+figcaption {{ font-size: 12px; overflow-wrap: anywhere; }}
+</style>
+</head>
+<body>
+<h1>{count} models</h1>
+<div class=\"sheet\">",
+        size = size,
+        count = thumbnails.len()
+    )
+    .unwrap();
+
+    for thumbnail in thumbnails {
+        let name = Path::new(&thumbnail.model).file_name().map_or_else(
+            || thumbnail.model.clone(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        let picture = match &thumbnail.image {
+            Some(image) => format!(
+                "<img src=\"{}\" alt=\"{}\">",
+                url_encode(image),
+                html_escape(&name)
+            ),
+            None => "<div class=\"missing\">no thumbnail</div>".to_string(),
+        };
+        let details = match (&thumbnail.stats, &thumbnail.error) {
+            (_, Some(error)) => html_escape(error),
+            (Some(stats), None) => format!(
+                "{} triangles, {:.2} x {:.2} x {:.2}",
+                stats.triangles, stats.size.x, stats.size.y, stats.size.z
+            ),
+            (None, None) => String::new(),
+        };
+        writeln!(
+            html,
+            "<figure>{}<figcaption title=\"{}\">{}<br>{}</figcaption></figure>",
+            picture,
+            html_escape(&thumbnail.model),
+            html_escape(&name),
+            details
+        )
+        .unwrap();
+    }
+
+    writeln!(html, "</div>\n</body>\n</html>").unwrap();
+    html
+}
+
+/// Percent encodes everything but letters, digits and `-._~`, for a file name used as a link.
+fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{UVec2, Vec3};
+
+    use super::*;
+
+    /// A model with a thumbnail, one that failed with characters to escape, and one with a name
+    /// that needs encoding as a link and sizes that need rounding.
+    fn fixture_thumbnails() -> Vec<Thumbnail> {
+        vec![
+            Thumbnail {
+                model: "assets/models/alien.glb".to_string(),
+                image: Some("alien.glb.png".to_string()),
+                stats: Some(ModelStats {
+                    path: "assets/models/alien.glb".to_string(),
+                    scene_index: 0,
+                    nodes: 12,
+                    meshes: 3,
+                    primitives: 4,
+                    triangles: 1280,
+                    vertices: 950,
+                    materials: 2,
+                    textures: vec![UVec2::new(512, 512)],
+                    animations: Vec::new(),
+                    skins: 1,
+                    joints: 18,
+                    size: Vec3::new(1.0, 2.5, 0.75),
+                }),
+                error: None,
+            },
+            Thumbnail {
+                model: "assets/models/<broken> & \"copy\".glb".to_string(),
+                image: None,
+                stats: None,
+                error: Some("invalid glTF: expected \"asset\" & <scenes>".to_string()),
+            },
+            Thumbnail {
+                model: "assets/models/tile #1.gltf".to_string(),
+                image: Some("tile #1.gltf.png".to_string()),
+                stats: Some(ModelStats {
+                    path: "assets/models/tile #1.gltf".to_string(),
+                    scene_index: 0,
+                    nodes: 1,
+                    meshes: 1,
+                    primitives: 1,
+                    triangles: 2,
+                    vertices: 4,
+                    materials: 0,
+                    textures: Vec::new(),
+                    animations: Vec::new(),
+                    skins: 0,
+                    joints: 0,
+                    size: Vec3::new(0.1 + 0.2, 0.0, 1.0 / 3.0),
+                }),
+                error: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn html_matches_the_golden_file() {
+        assert_eq!(
+            html_index(&fixture_thumbnails(), 128),
+            include_str!("../assets/tests/thumbnails.html")
+        );
+    }
+
+    #[test]
+    fn json_matches_the_golden_file() {
+        assert_eq!(
+            json_index(&fixture_thumbnails(), 128),
+            include_str!("../assets/tests/thumbnails.json")
+        );
+    }
+
+    #[test]
+    fn url_encode_keeps_only_unreserved_characters() {
+        assert_eq!(url_encode("Tile_2-b.glb~.png"), "Tile_2-b.glb~.png");
+        assert_eq!(
+            url_encode("a b/<c>&\"d\"#?%.png"),
+            "a%20b%2F%3Cc%3E%26%22d%22%23%3F%25.png"
+        );
+        assert_eq!(url_encode("\u{e9}.png"), "%C3%A9.png");
+    }
+
+    #[test]
+    fn image_name_adds_png_to_the_file_name() {
+        assert_eq!(image_name("assets/models/alien.glb"), "alien.glb.png");
+        assert_eq!(image_name("alien.gltf"), "alien.gltf.png");
+        assert_eq!(image_name("models/tile #1.gltf"), "tile #1.gltf.png");
+        assert_eq!(image_name(".."), "...png");
+    }
+}
@@ -0,0 +1,478 @@
+//! Turns the orbit camera once around its focus, in the viewer or frame by frame to export the turn
+//! as numbered PNGs and optionally a GIF.
+//!
+//! J starts or stops a turn at a constant rate. `--turntable DIR` poses the camera for each frame in
+//! turn, saves it with `TakeScreenshot`, and quits after the last one.
+
+use std::f32::consts::TAU;
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    app::AppExit,
+    prelude::*,
+    render::texture::{CompressedImageFormats, ImageType},
+};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+
+use crate::args::ViewerArgs;
+use crate::camera_pan_orbit::PanOrbitCamera;
+use crate::gif::encode_gif;
+use crate::scene_setup::SceneSetup;
+use crate::screenshot::{ScreenshotSaved, TakeScreenshot};
+
+/// How long one turn takes in the viewer, and in the exported GIF.
+pub const TURN_SECONDS: f32 = 8.0;
+
+/// Where an orbiting camera is around its focus, in radians.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitAngles {
+    /// Around the vertical axis, zero on the +Z side of the focus.
+    pub azimuth: f32,
+    /// Above the focus, positive looks down on it.
+    pub elevation: f32,
+}
+
+impl OrbitAngles {
+    /// The angles of a camera at `translation` orbiting `focus`.
+    pub fn from_position(translation: Vec3, focus: Vec3) -> Self {
+        let offset = translation - focus;
+        OrbitAngles {
+            azimuth: offset.x.atan2(offset.z),
+            elevation: offset.y.atan2(Vec2::new(offset.x, offset.z).length()),
+        }
+    }
+
+    /// The rotation of a camera at these angles looking at its focus.
+    pub fn rotation(&self) -> Quat {
+        Quat::from_rotation_y(self.azimuth) * Quat::from_rotation_x(-self.elevation)
+    }
+}
+
+/// The start of a turn, with the `--turntable-elevation` and `--turntable-radius` given on the
+/// command line in place of the camera's own.
+fn turn_start(
+    viewer_args: &ViewerArgs,
+    pan_orbit: &PanOrbitCamera,
+    transform: &Transform,
+) -> (OrbitAngles, f32) {
+    let mut angles = OrbitAngles::from_position(transform.translation, pan_orbit.focus);
+    if let Some(elevation) = viewer_args.turntable_elevation {
+        angles.elevation = elevation.to_radians();
+    }
+    let radius = viewer_args.turntable_radius.unwrap_or(pan_orbit.radius);
+    (angles, radius)
+}
+
+/// Moves an orbit camera to `angles` and `radius` around its focus, pushing the far plane out if
+/// the scene would no longer fit.
+fn pose_camera(
+    pan_orbit: &mut PanOrbitCamera,
+    transform: &mut Transform,
+    projection: &mut Projection,
+    angles: OrbitAngles,
+    radius: f32,
+) {
+    pan_orbit.radius = radius;
+    pan_orbit.upside_down = false;
+    transform.rotation = angles.rotation();
+    transform.translation = pan_orbit.focus + transform.rotation * Vec3::new(0.0, 0.0, radius);
+    if let Projection::Perspective(perspective) = projection {
+        perspective.far = perspective.far.max(radius * 10.0);
+    }
+}
+
+struct Turn {
+    start: OrbitAngles,
+    radius: f32,
+    /// Radians turned so far.
+    turned: f32,
+}
+
+/// The turn J started, if it is still going.
+#[derive(Resource, Default)]
+pub struct Turntable {
+    turn: Option<Turn>,
+}
+
+impl Turntable {
+    /// Leaves the camera wherever the turn has got to.
+    pub fn stop(&mut self) {
+        self.turn = None;
+    }
+}
+
+/// J turns the active orbit camera once around its focus at a constant rate. Pressing J again or
+/// orbiting with the mouse stops it.
+pub fn turn_turntable(
+    mut contexts: EguiContexts,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    time: Res<Time>,
+    viewer_args: Res<ViewerArgs>,
+    mut turntable: ResMut<Turntable>,
+    mut cameras: Query<(
+        &Camera,
+        &mut PanOrbitCamera,
+        &mut Transform,
+        &mut Projection,
+    )>,
+) {
+    let Some((_, mut pan_orbit, mut transform, mut projection)) =
+        cameras.iter_mut().find(|(camera, ..)| camera.is_active)
+    else {
+        turntable.turn = None;
+        return;
+    };
+
+    if !contexts.ctx_mut().wants_keyboard_input() && keyboard_input.just_pressed(KeyCode::J) {
+        turntable.turn = match turntable.turn {
+            Some(_) => None,
+            None => {
+                let (start, radius) = turn_start(&viewer_args, &pan_orbit, &transform);
+                Some(Turn {
+                    start,
+                    radius,
+                    turned: 0.0,
+                })
+            }
+        };
+    }
+    if mouse_input.pressed(MouseButton::Right) {
+        turntable.turn = None;
+    }
+    let Some(turn) = &mut turntable.turn else {
+        return;
+    };
+
+    // stop exactly where the turn started
+    turn.turned = (turn.turned + TAU / TURN_SECONDS * time.delta_seconds()).min(TAU);
+    let angles = OrbitAngles {
+        azimuth: turn.start.azimuth + turn.turned,
+        ..turn.start
+    };
+    pose_camera(
+        &mut pan_orbit,
+        &mut transform,
+        &mut projection,
+        angles,
+        turn.radius,
+    );
+    if turn.turned >= TAU {
+        turntable.turn = None;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportState {
+    /// Waiting for the models to be framed.
+    Loading,
+    /// Ready to move the camera to the next frame.
+    Pose,
+    /// The camera has moved, the screenshot is taken once its transform has been propagated.
+    Shoot,
+    /// Waiting for the frame to be written.
+    Capturing,
+}
+
+#[derive(Resource)]
+pub struct TurntableExport {
+    folder: PathBuf,
+    frames: u32,
+    /// In pixels, the window size when `None`.
+    size: Option<UVec2>,
+    transparent: bool,
+    gif: bool,
+    state: ExportState,
+    start: Option<(OrbitAngles, f32)>,
+    /// Frames written so far.
+    saved: Vec<PathBuf>,
+    failed: u32,
+}
+
+impl TurntableExport {
+    /// An export of `frames` frames to `folder`, which is created.
+    pub fn new(
+        folder: &str,
+        frames: u32,
+        size: Option<UVec2>,
+        transparent: bool,
+        gif: bool,
+    ) -> std::io::Result<Self> {
+        std::fs::create_dir_all(folder)?;
+        Ok(TurntableExport {
+            folder: PathBuf::from(folder),
+            frames,
+            size,
+            transparent,
+            gif,
+            state: ExportState::Loading,
+            start: None,
+            saved: Vec::new(),
+            failed: 0,
+        })
+    }
+
+    fn frame(&self) -> u32 {
+        self.saved.len() as u32 + self.failed
+    }
+
+    fn write_gif(&self) -> Result<PathBuf, String> {
+        let mut size = UVec2::ZERO;
+        let mut frames = Vec::new();
+        for path in &self.saved {
+            let image = read_png(path)?;
+            let frame_size = image.size().as_uvec2();
+            if size != UVec2::ZERO && frame_size != size {
+                return Err(format!("{} is a different size", path.display()));
+            }
+            size = frame_size;
+            frames.push(image.data);
+        }
+        if size.max_element() > u16::MAX as u32 {
+            return Err(format!("{}x{} is too large for a GIF", size.x, size.y));
+        }
+
+        let gif = encode_gif(
+            &frames,
+            size.x as u16,
+            size.y as u16,
+            gif_delay(self.frames),
+            self.transparent,
+        );
+        let path = self.folder.join("turntable.gif");
+        std::fs::write(&path, gif).map_err(|error| error.to_string())?;
+        Ok(path)
+    }
+}
+
+/// `frame_0007.png`.
+fn frame_name(frame: u32) -> String {
+    format!("frame_{:04}.png", frame)
+}
+
+/// Where `frame` of `frames` is in the turn. The last frame stops a step short of the first, so the
+/// frames loop.
+fn frame_angles(start: OrbitAngles, frame: u32, frames: u32) -> OrbitAngles {
+    OrbitAngles {
+        azimuth: start.azimuth + TAU * frame as f32 / frames as f32,
+        ..start
+    }
+}
+
+/// How long the GIF shows each of `frames` frames so a turn takes `TURN_SECONDS`, in the hundredths
+/// of a second GIF delays are in. Most viewers slow down anything under 2.
+fn gif_delay(frames: u32) -> u16 {
+    (TURN_SECONDS * 100.0 / frames as f32).round().max(2.0) as u16
+}
+
+fn read_png(path: &Path) -> Result<Image, String> {
+    let bytes = std::fs::read(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+    )
+    .map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+/// Once the models are framed, saves a frame at each step of one turn of the orbit camera, then
+/// writes the GIF if asked for and quits, with a non-zero exit status when a frame or the GIF
+/// could not be written.
+#[allow(clippy::too_many_arguments)]
+pub fn run_turntable_export(
+    mut turntable_export: ResMut<TurntableExport>,
+    scene_setup: Res<SceneSetup>,
+    viewer_args: Res<ViewerArgs>,
+    mut cameras: Query<(
+        &Camera,
+        &mut PanOrbitCamera,
+        &mut Transform,
+        &mut Projection,
+    )>,
+    mut take_screenshot: EventWriter<TakeScreenshot>,
+    mut screenshot_saved: EventReader<ScreenshotSaved>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    let export = &mut *turntable_export;
+    match export.state {
+        ExportState::Loading => {
+            if !scene_setup.is_done() {
+                return;
+            }
+            // the camera is spawned by a command, so it may only be here next frame
+            let Some((_, pan_orbit, transform, _)) =
+                cameras.iter().find(|(camera, ..)| camera.is_active)
+            else {
+                return;
+            };
+            export.start = Some(turn_start(&viewer_args, pan_orbit, transform));
+            export.state = ExportState::Pose;
+        }
+        ExportState::Pose if export.frame() == export.frames => {
+            let mut failed = export.failed > 0;
+            if export.gif && !failed {
+                match export.write_gif() {
+                    Ok(path) => info!("Saved the turntable GIF to {}", path.display()),
+                    Err(error) => {
+                        error!("Could not write the turntable GIF: {}", error);
+                        failed = true;
+                    }
+                }
+            } else if export.gif {
+                error!("Not writing the turntable GIF, some frames are missing");
+            }
+            info!(
+                "Saved {} turntable frames to {}, {} failed",
+                export.saved.len(),
+                export.folder.display(),
+                export.failed
+            );
+            if failed {
+                std::process::exit(1);
+            }
+            app_exit.send(AppExit);
+        }
+        ExportState::Pose => {
+            let (Some((start, radius)), Some((_, mut pan_orbit, mut transform, mut projection))) = (
+                export.start,
+                cameras.iter_mut().find(|(camera, ..)| camera.is_active),
+            ) else {
+                return;
+            };
+            let angles = frame_angles(start, export.frame(), export.frames);
+            pose_camera(
+                &mut pan_orbit,
+                &mut transform,
+                &mut projection,
+                angles,
+                radius,
+            );
+            export.state = ExportState::Shoot;
+        }
+        ExportState::Shoot => {
+            take_screenshot.send(TakeScreenshot {
+                path: export.folder.join(frame_name(export.frame())),
+                size: export.size,
+                transparent: export.transparent,
+            });
+            export.state = ExportState::Capturing;
+        }
+        ExportState::Capturing => {
+            let path = export.folder.join(frame_name(export.frame()));
+            let Some(saved) = screenshot_saved.iter().find(|saved| saved.path == path) else {
+                return;
+            };
+            match saved.error {
+                None => export.saved.push(saved.path.clone()),
+                Some(_) => export.failed += 1,
+            }
+            export.state = ExportState::Pose;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    use super::*;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() <= 1e-5 * expected.abs().max(1.0),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn angles_from_position() {
+        let focus = Vec3::new(1.0, 2.0, 3.0);
+        let front = OrbitAngles::from_position(focus + Vec3::Z, focus);
+        assert_near(front.azimuth, 0.0);
+        assert_near(front.elevation, 0.0);
+
+        let right_above = OrbitAngles::from_position(focus + Vec3::new(2.0, 2.0, 0.0), focus);
+        assert_near(right_above.azimuth, FRAC_PI_2);
+        assert_near(right_above.elevation, FRAC_PI_4);
+
+        let behind_below = OrbitAngles::from_position(focus + Vec3::new(0.0, -1.0, -1.0), focus);
+        assert_near(behind_below.azimuth.abs(), PI);
+        assert_near(behind_below.elevation, -FRAC_PI_4);
+    }
+
+    #[test]
+    fn rotation_looks_at_the_focus_from_the_angles() {
+        let angles = OrbitAngles {
+            azimuth: 2.0,
+            elevation: 0.5,
+        };
+        let back = angles.rotation() * Vec3::Z;
+        let round_trip = OrbitAngles::from_position(back, Vec3::ZERO);
+        assert_near(round_trip.azimuth, angles.azimuth);
+        assert_near(round_trip.elevation, angles.elevation);
+        // level, so the horizon stays level
+        assert_near((angles.rotation() * Vec3::X).y, 0.0);
+    }
+
+    #[test]
+    fn pose_camera_moves_to_the_angles() {
+        let mut pan_orbit = PanOrbitCamera {
+            focus: Vec3::new(1.0, 2.0, 3.0),
+            radius: 5.0,
+            upside_down: true,
+        };
+        let mut transform = Transform::default();
+        let mut projection = Projection::Perspective(PerspectiveProjection {
+            far: 20.0,
+            ..default()
+        });
+        let angles = OrbitAngles {
+            azimuth: -1.0,
+            elevation: 0.3,
+        };
+        pose_camera(&mut pan_orbit, &mut transform, &mut projection, angles, 4.0);
+
+        assert_eq!(pan_orbit.radius, 4.0);
+        assert!(!pan_orbit.upside_down);
+        assert_near(transform.translation.distance(pan_orbit.focus), 4.0);
+        let posed = OrbitAngles::from_position(transform.translation, pan_orbit.focus);
+        assert_near(posed.azimuth, angles.azimuth);
+        assert_near(posed.elevation, angles.elevation);
+        let looking_at = transform.translation + transform.forward() * 4.0;
+        assert!(looking_at.abs_diff_eq(pan_orbit.focus, 1e-5));
+        let Projection::Perspective(perspective) = projection else {
+            unreachable!();
+        };
+        assert_near(perspective.far, 40.0);
+    }
+
+    #[test]
+    fn frames_stop_a_step_short_of_a_turn() {
+        let start = OrbitAngles {
+            azimuth: 0.5,
+            elevation: 0.2,
+        };
+        assert_eq!(frame_angles(start, 0, 36), start);
+        assert_near(frame_angles(start, 1, 36).azimuth, 0.5 + TAU / 36.0);
+        let last = frame_angles(start, 35, 36);
+        assert_near(last.azimuth, 0.5 + TAU - TAU / 36.0);
+        assert_eq!(last.elevation, start.elevation);
+        // the step from the last frame back to the first is a step like any other
+        assert_near(
+            frame_angles(start, 36, 36).azimuth - last.azimuth,
+            TAU / 36.0,
+        );
+    }
+
+    #[test]
+    fn gif_delay_adds_up_to_a_turn() {
+        assert_eq!(gif_delay(1), 800);
+        assert_eq!(gif_delay(80), 10);
+        assert_eq!(gif_delay(36), 22);
+        assert_eq!(gif_delay(400), 2);
+        assert_eq!(gif_delay(1000), 2);
+    }
+}
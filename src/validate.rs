@@ -0,0 +1,339 @@
+//! Checks a library of models without opening a window, for use before committing assets.
+//!
+//! Every model is loaded and spawned by the same systems the viewer uses, so anything the viewer
+//! can't show fails here too. The files themselves are read as well, for what the Bevy loader
+//! hides: it fills in missing normals, and a missing buffer only shows up as a failed load.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin, ScheduleRunnerSettings},
+    log::{Level, LogPlugin},
+    prelude::*,
+    render::{settings::WgpuSettings, RenderPlugin},
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+
+use crate::args::ViewerArgs;
+use crate::bounds::SceneBounds;
+use crate::model_grid::{arrange_models, ModelGrid};
+use crate::model_stats::{collect_model_stats, ModelStats};
+use crate::scene_setup::{
+    asset_folder, load_models, scene_load_check, setup_scene, LoadModels, LoadStatus, SceneHandle,
+    SceneSetup,
+};
+
+/// Seconds to wait for the models to load before the stragglers are reported as errors.
+pub const VALIDATE_TIMEOUT_SECS: f32 = 60.0;
+
+/// Scales further than this from one are reported.
+const SCALE_TOLERANCE: f32 = 1e-4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Issue {
+    fn error(message: String) -> Self {
+        Issue {
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    fn warning(message: String) -> Self {
+        Issue {
+            severity: Severity::Warning,
+            message,
+        }
+    }
+}
+
+/// What was found wrong with one model, nothing when it passed, and its statistics if it loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelReport {
+    pub path: String,
+    pub issues: Vec<Issue>,
+    pub stats: Option<ModelStats>,
+}
+
+impl ModelReport {
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.severity == Severity::Error)
+    }
+}
+
+/// Set by `report_validation` when any model has an error, read once the app has exited.
+#[derive(Resource, Clone, Default)]
+struct ValidationFailed(Arc<AtomicBool>);
+
+/// Loads the models in `viewer_args` headless, prints what is wrong with each and returns whether
+/// they all passed. Warnings don't fail the run. With `--report` the results are also written as
+/// JSON or CSV, to stdout without any logging when there is no `--report-file`.
+pub fn run_validation(viewer_args: ViewerArgs) -> bool {
+    let failed = ValidationFailed::default();
+    let report_to_stdout = viewer_args.report.is_some() && viewer_args.report_file.is_none();
+
+    let mut plugins = DefaultPlugins
+        .set(WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            close_when_requested: false,
+        })
+        .set(AssetPlugin {
+            asset_folder: asset_folder(),
+            watch_for_changes: false,
+        })
+        // meshes and textures are still loaded, just never uploaded
+        .set(RenderPlugin {
+            wgpu_settings: WgpuSettings {
+                backends: None,
+                ..default()
+            },
+        })
+        .set(LogPlugin {
+            level: Level::WARN,
+            ..default()
+        })
+        .disable::<WinitPlugin>();
+    if report_to_stdout {
+        // the log goes to stdout as well
+        plugins = plugins.disable::<LogPlugin>();
+    }
+
+    App::new()
+        .insert_resource(viewer_args)
+        .insert_resource(failed.clone())
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+            1.0 / 60.0,
+        )))
+        .init_resource::<ModelGrid>()
+        .init_resource::<SceneSetup>()
+        .init_resource::<SceneBounds>()
+        .add_event::<LoadModels>()
+        .add_plugins(plugins)
+        .add_plugin(ScheduleRunnerPlugin)
+        .add_startup_system(setup_scene)
+        .add_system(load_models)
+        .add_system(scene_load_check)
+        .add_system(arrange_models)
+        .add_system(collect_model_stats)
+        .add_system(report_validation)
+        .run();
+
+    !failed.0.load(Ordering::Relaxed)
+}
+
+/// Once every model has loaded and has its statistics, or failed, prints the report and exits.
+fn report_validation(
+    time: Res<Time>,
+    viewer_args: Res<ViewerArgs>,
+    model_grid: Res<ModelGrid>,
+    scene_handles: Query<(&SceneHandle, Option<&ModelStats>)>,
+    failed: Res<ValidationFailed>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    let done = model_grid.is_arranged()
+        && scene_handles
+            .iter()
+            .all(|(scene_handle, stats)| !scene_handle.is_loaded() || stats.is_some());
+    let timed_out = time.elapsed_seconds() > VALIDATE_TIMEOUT_SECS;
+    if scene_handles.is_empty() || !(done || timed_out) {
+        return;
+    }
+
+    let mut reports: Vec<ModelReport> = scene_handles
+        .iter()
+        .map(|(scene_handle, stats)| model_report(scene_handle, stats))
+        .collect();
+    reports.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut has_errors = reports.iter().any(ModelReport::has_errors);
+    match (viewer_args.report, &viewer_args.report_file) {
+        (Some(format), None) => print!("{}", format.write(&reports)),
+        (Some(format), Some(report_file)) => {
+            print_reports(&reports);
+            match std::fs::write(report_file, format.write(&reports)) {
+                Ok(()) => println!("Report written to {}", report_file),
+                Err(error) => {
+                    eprintln!("error: could not write {}: {}", report_file, error);
+                    has_errors = true;
+                }
+            }
+        }
+        (None, _) => print_reports(&reports),
+    }
+    failed.0.store(has_errors, Ordering::Relaxed);
+    app_exit.send(AppExit);
+}
+
+fn model_report(scene_handle: &SceneHandle, stats: Option<&ModelStats>) -> ModelReport {
+    let mut issues = check_file(scene_handle.path());
+
+    match scene_handle.status() {
+        LoadStatus::Failed { reason, .. } => {
+            issues.push(Issue::error(format!("failed to load: {}", reason)));
+        }
+        LoadStatus::Loaded => match scene_handle.bounds() {
+            Some(bounds) if bounds.half_extents.max_element() <= f32::EPSILON => {
+                issues.push(Issue::error("bounds have zero size".to_string()));
+            }
+            Some(_) => {}
+            None => issues.push(Issue::error("timed out working out the bounds".to_string())),
+        },
+        status => issues.push(Issue::error(format!("timed out while {:?}", status))),
+    }
+
+    issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+    ModelReport {
+        path: scene_handle.path().to_string(),
+        issues,
+        stats: stats.cloned(),
+    }
+}
+
+/// Reads the glTF document of the model at `path` in the asset folder and reports missing external
+/// files, primitives without normals or UVs, and nodes that are scaled. A file that can't be read
+/// at all is left to the loader to report.
+pub fn check_file(path: &str) -> Vec<Issue> {
+    let full_path = Path::new(&asset_folder()).join(path);
+    let Ok(document) = gltf::Gltf::open(&full_path) else {
+        return Vec::new();
+    };
+    let folder = full_path.parent().unwrap_or(Path::new("."));
+    let is_missing =
+        |uri: &str| !uri.starts_with("data:") && !folder.join(decode_uri(uri)).is_file();
+
+    let mut issues = Vec::new();
+    for buffer in document.buffers() {
+        if let gltf::buffer::Source::Uri(uri) = buffer.source() {
+            if is_missing(uri) {
+                issues.push(Issue::error(format!("missing buffer {}", uri)));
+            }
+        }
+    }
+    for image in document.images() {
+        if let gltf::image::Source::Uri { uri, .. } = image.source() {
+            if is_missing(uri) {
+                issues.push(Issue::error(format!("missing image {}", uri)));
+            }
+        }
+    }
+
+    for mesh in document.meshes() {
+        let name = mesh
+            .name()
+            .map_or_else(|| format!("#{}", mesh.index()), str::to_string);
+        for primitive in mesh.primitives() {
+            let triangles = matches!(
+                primitive.mode(),
+                gltf::mesh::Mode::Triangles
+                    | gltf::mesh::Mode::TriangleStrip
+                    | gltf::mesh::Mode::TriangleFan
+            );
+            if !triangles {
+                continue;
+            }
+            if primitive.get(&gltf::Semantic::Normals).is_none() {
+                issues.push(Issue::warning(format!(
+                    "mesh {} primitive {} has no normals",
+                    name,
+                    primitive.index()
+                )));
+            }
+            if primitive.get(&gltf::Semantic::TexCoords(0)).is_none() {
+                issues.push(Issue::warning(format!(
+                    "mesh {} primitive {} has no UVs",
+                    name,
+                    primitive.index()
+                )));
+            }
+        }
+    }
+
+    for node in document.nodes() {
+        let (_, _, scale) = node.transform().decomposed();
+        if scale.iter().any(|s| (s - 1.0).abs() > SCALE_TOLERANCE) {
+            issues.push(Issue::warning(format!(
+                "node {} has scale [{}, {}, {}]",
+                node.name()
+                    .map_or_else(|| format!("#{}", node.index()), str::to_string),
+                scale[0],
+                scale[1],
+                scale[2]
+            )));
+        }
+    }
+    issues
+}
+
+/// Undoes the percent encoding of a relative URI, e.g. `my%20model.bin`.
+fn decode_uri(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn print_reports(reports: &[ModelReport]) {
+    for report in reports {
+        if report.issues.is_empty() {
+            println!("ok      {}", report.path);
+            continue;
+        }
+        let verdict = if report.has_errors() { "FAILED" } else { "ok" };
+        println!("{:<7} {}", verdict, report.path);
+        for issue in &report.issues {
+            let severity = match issue.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            println!("        {}: {}", severity, issue.message);
+        }
+    }
+
+    let count = |severity| {
+        reports
+            .iter()
+            .flat_map(|report| &report.issues)
+            .filter(|issue| issue.severity == severity)
+            .count()
+    };
+    println!(
+        "\n{} models, {} failed, {} errors, {} warnings",
+        reports.len(),
+        reports.iter().filter(|report| report.has_errors()).count(),
+        count(Severity::Error),
+        count(Severity::Warning)
+    );
+}
@@ -0,0 +1,51 @@
+//! Text drawn with egui at positions in the 3D world.
+
+use std::hash::Hash;
+
+use bevy::ecs::query::ReadOnlyWorldQuery;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy_inspector_egui::egui;
+
+/// Draws `text` centered just above the point `position` as seen by `camera`. Nothing is drawn
+/// for points behind the camera.
+pub fn world_label(
+    ctx: &egui::Context,
+    id: impl Hash,
+    (camera, camera_transform): (&Camera, &GlobalTransform),
+    position: Vec3,
+    text: impl Into<egui::WidgetText>,
+) {
+    let Some((viewport_min, viewport_max)) = camera.logical_viewport_rect() else {
+        return;
+    };
+    let Some(screen_position) = camera.world_to_viewport(camera_transform, position) else {
+        return;
+    };
+
+    // viewport coordinates start at the bottom left of the viewport, egui's at the top left of
+    // the window
+    egui::Area::new(egui::Id::new(id))
+        .fixed_pos(egui::pos2(
+            viewport_min.x + screen_position.x,
+            viewport_max.y - screen_position.y,
+        ))
+        .pivot(egui::Align2::CENTER_BOTTOM)
+        .order(egui::Order::Background)
+        .interactable(false)
+        .show(ctx, |ui| {
+            ui.label(text);
+        });
+}
+
+/// The camera that is currently rendering to the whole window, if any. Cameras drawing to part of
+/// it, like the orientation gizmo's, or to an image, like a screenshot's, are skipped.
+pub fn active_camera<'a, F: ReadOnlyWorldQuery>(
+    cameras: &'a Query<(&Camera, &GlobalTransform), F>,
+) -> Option<(&'a Camera, &'a GlobalTransform)> {
+    cameras.iter().find(|(camera, _)| {
+        camera.is_active
+            && camera.viewport.is_none()
+            && matches!(camera.target, RenderTarget::Window(_))
+    })
+}